@@ -7,13 +7,53 @@ use near_sdk::json_types::U64;
 
 const ACTION_LOG_SIZE: usize = 20;
 
+/// Action recorded in the actions log.
+/// Mirrors `Action` with the same borsh discriminants, so logs stored before keep their meaning,
+/// and adds changes of the proposal by its proposer, which aren't subject to the policy.
+#[derive(Clone, Copy)]
+#[near(serializers=[borsh(use_discriminant = true), json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub enum LoggedAction {
+    AddProposal = 0,
+    RemoveProposal = 1,
+    VoteApprove = 2,
+    VoteReject = 3,
+    VoteRemove = 4,
+    Finalize = 5,
+    MoveToHub = 6,
+    Execute = 7,
+    Cancel = 8,
+    Veto = 9,
+    /// Proposal was amended by its proposer.
+    AmendProposal = 10,
+    /// Proposal was withdrawn by its proposer.
+    WithdrawProposal = 11,
+}
+
+impl From<Action> for LoggedAction {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::AddProposal => Self::AddProposal,
+            Action::RemoveProposal => Self::RemoveProposal,
+            Action::VoteApprove => Self::VoteApprove,
+            Action::VoteReject => Self::VoteReject,
+            Action::VoteRemove => Self::VoteRemove,
+            Action::Finalize => Self::Finalize,
+            Action::MoveToHub => Self::MoveToHub,
+            Action::Execute => Self::Execute,
+            Action::Cancel => Self::Cancel,
+            Action::Veto => Self::Veto,
+        }
+    }
+}
+
 #[derive(Clone)]
 #[near(serializers=[borsh, json])]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 pub struct ActionLog {
     pub account_id: AccountId,
     pub proposal_id: U64,
-    pub action: Action,
+    pub action: LoggedAction,
     pub block_height: U64,
 }
 
@@ -36,7 +76,18 @@ impl Contract {
     pub(crate) fn internal_log_action(
         &mut self,
         proposal_id: u64,
-        action: Action,
+        action: impl Into<LoggedAction>,
+        proposal: &mut Proposal,
+    ) {
+        self.internal_log_action_by(env::predecessor_account_id(), proposal_id, action, proposal);
+    }
+
+    /// Logs action on behalf of given account, e.g. the sender of tokens in `ft_on_transfer`.
+    pub(crate) fn internal_log_action_by(
+        &mut self,
+        account_id: AccountId,
+        proposal_id: u64,
+        action: impl Into<LoggedAction>,
         proposal: &mut Proposal,
     ) {
         update_action_log(
@@ -48,9 +99,9 @@ impl Contract {
         update_action_log(
             &mut self.actions_log,
             ActionLog {
-                account_id: env::predecessor_account_id(),
+                account_id,
                 proposal_id: proposal_id.into(),
-                action,
+                action: action.into(),
                 block_height: env::block_height().into(),
             },
         );
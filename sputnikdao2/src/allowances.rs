@@ -0,0 +1,308 @@
+use near_sdk::json_types::{U64, U128};
+use near_sdk::{AccountId, Promise, PromiseOrValue, env, utils};
+
+use crate::types::{GAS_FOR_FT_TRANSFER, OldAccountId, convert_old_to_new_token};
+use crate::*;
+
+/// Spending allowance created by an approved `GrantAllowance` proposal.
+#[near(serializers=[borsh, json])]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(deny_unknown_fields)]
+pub struct Allowance {
+    /// Account that can spend the allowance.
+    pub spender: AccountId,
+    /// Token the allowance is spent in.
+    /// Can be "" for $NEAR or a valid account id.
+    pub token_id: OldAccountId,
+    /// Amount that can be spent per period.
+    pub amount: U128,
+    /// Length of a period in nanoseconds.
+    pub period: U64,
+    /// Time after which the allowance can't be spent anymore. Never expires if not set.
+    pub expires_at: Option<U64>,
+    /// Start of the first period, i.e. when the proposal got executed.
+    pub start_time: U64,
+    /// Period of the last spending, counted from the start time.
+    pub spent_period: u64,
+    /// Amount spent in that period.
+    pub spent: U128,
+}
+
+impl Allowance {
+    fn current_period(&self) -> u64 {
+        env::block_timestamp().saturating_sub(self.start_time.0) / self.period.0
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| env::block_timestamp() > expires_at.0)
+    }
+
+    /// Amount that can still be spent in the current period.
+    pub(crate) fn remaining(&self) -> Balance {
+        if self.spent_period == self.current_period() {
+            self.amount.0.saturating_sub(self.spent.0)
+        } else {
+            self.amount.0
+        }
+    }
+}
+
+impl Contract {
+    /// Adds allowance to the storage and returns it's id.
+    /// Replaces the allowance the spender already had for the same token.
+    pub(crate) fn internal_add_allowance(
+        &mut self,
+        spender: &AccountId,
+        token_id: &OldAccountId,
+        amount: U128,
+        period: U64,
+        expires_at: Option<U64>,
+    ) -> u64 {
+        let id = self.last_allowance_id;
+        if let Some(old_id) = self
+            .allowance_ids
+            .insert(&(spender.clone(), token_id.clone()), &id)
+        {
+            self.allowances.remove(&old_id);
+        }
+        self.allowances.insert(
+            &id,
+            &Allowance {
+                spender: spender.clone(),
+                token_id: token_id.clone(),
+                amount,
+                period,
+                expires_at,
+                start_time: U64::from(env::block_timestamp()),
+                spent_period: 0,
+                spent: U128(0),
+            },
+        );
+        self.last_allowance_id += 1;
+        id
+    }
+
+    /// Removes allowance from the storage.
+    pub(crate) fn internal_remove_allowance(&mut self, id: u64) {
+        if let Some(allowance) = self.allowances.remove(&id) {
+            self.allowance_ids
+                .remove(&(allowance.spender, allowance.token_id));
+        }
+    }
+}
+
+#[near]
+impl Contract {
+    /// Pays out given amount of the token to the receiver from the allowance of the caller.
+    /// Fails if the allowance has expired or the amount exceeds what is left for the current period.
+    /// Storage of the receiver is never paid from the treasury here, even if the policy sets
+    /// `ft_storage_deposit`, so the receiver must already be registered with the token.
+    /// Requires attached deposit of 1 yoctoNEAR.
+    #[payable]
+    pub fn spend_allowance(
+        &mut self,
+        token_id: OldAccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) -> PromiseOrValue<()> {
+        near_sdk::assert_one_yocto();
+        assert!(amount.0 > 0, "ERR_INVALID_AMOUNT");
+        let id = self
+            .allowance_ids
+            .get(&(env::predecessor_account_id(), token_id.clone()))
+            .expect("ERR_NO_ALLOWANCE");
+        let mut allowance = self.allowances.get(&id).unwrap();
+        assert!(!allowance.is_expired(), "ERR_ALLOWANCE_EXPIRED");
+        assert!(amount.0 <= allowance.remaining(), "ERR_ALLOWANCE_EXCEEDED");
+        let period = allowance.current_period();
+        allowance.spent = U128(allowance.amount.0 - allowance.remaining() + amount.0);
+        allowance.spent_period = period;
+        self.allowances.insert(&id, &allowance);
+
+        let memo = memo.unwrap_or_else(|| format!("Allowance {} spending", id));
+        let payout = match convert_old_to_new_token(&token_id) {
+            Some(ft_token_id) => {
+                Self::internal_ft_transfer(&ft_token_id, &receiver_id, amount.0, memo, None)
+            }
+            None => Promise::new(receiver_id).transfer(NearToken::from_yoctonear(amount.0)),
+        };
+        payout
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER)
+                    .on_spend_allowance_callback(id, token_id, period, amount),
+            )
+            .into()
+    }
+
+    /// Callback after the allowance spending.
+    /// If the payout failed, the amount can be spent again.
+    #[private]
+    pub fn on_spend_allowance_callback(
+        &mut self,
+        allowance_id: u64,
+        token_id: OldAccountId,
+        period: u64,
+        amount: U128,
+    ) {
+        if utils::is_promise_success() {
+            self.internal_debit_token(&token_id, amount.0);
+            return;
+        }
+        // Allowance may have been revoked or the period has passed in the meantime.
+        if let Some(mut allowance) = self.allowances.get(&allowance_id) {
+            if allowance.spent_period == period {
+                allowance.spent = U128(allowance.spent.0.saturating_sub(amount.0));
+                self.allowances.insert(&allowance_id, &allowance);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_api::types::NearToken;
+    use near_sdk::test_utils::{VMContextBuilder, accounts};
+    use near_sdk::{PromiseResult, testing_env};
+
+    use crate::Config;
+    use crate::proposals::ProposalKind;
+
+    use super::*;
+    use crate::tests::add_approved_proposal;
+
+    const PERIOD: u64 = 1_000_000_000 * 60 * 60 * 24 * 30;
+
+    fn execute(context: &mut VMContextBuilder, contract: &mut Contract, kind: ProposalKind) {
+        context.predecessor_account_id(accounts(1));
+        add_approved_proposal(context, contract, kind);
+    }
+
+    fn grant_allowance(context: &mut VMContextBuilder, contract: &mut Contract) -> u64 {
+        execute(
+            context,
+            contract,
+            ProposalKind::GrantAllowance {
+                spender: accounts(2),
+                token_id: accounts(4).to_string(),
+                amount: U128(50),
+                period: U64(PERIOD),
+                expires_at: Some(U64(PERIOD * 2)),
+            },
+        );
+        contract.get_last_allowance_id() - 1
+    }
+
+    fn spend(context: &mut VMContextBuilder, contract: &mut Contract, amount: u128) {
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(2))
+                .attached_deposit(NearToken::from_yoctonear(1))
+                .build()
+        );
+        let _ = contract.spend_allowance(accounts(4).to_string(), accounts(3), U128(amount), None);
+    }
+
+    #[test]
+    fn test_spend_allowance() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = grant_allowance(&mut context, &mut contract);
+        spend(&mut context, &mut contract, 30);
+        assert_eq!(contract.get_allowance(id).remaining, U128(20));
+
+        // Failed payout can be spent again.
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_spend_allowance_callback(id, accounts(4).to_string(), 0, U128(30));
+        assert_eq!(contract.get_allowance(id).remaining, U128(50));
+
+        // Budget is reset every period.
+        spend(&mut context, &mut contract, 50);
+        testing_env!(context.block_timestamp(PERIOD).build());
+        assert_eq!(contract.get_allowance(id).remaining, U128(50));
+        spend(&mut context, &mut contract, 40);
+        assert_eq!(contract.get_allowance(id).remaining, U128(10));
+
+        // Expired allowances are not listed.
+        assert_eq!(contract.get_allowances(0, 10).len(), 1);
+        testing_env!(context.block_timestamp(PERIOD * 2 + 1).build());
+        assert!(contract.get_allowances(0, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALLOWANCE_EXCEEDED")]
+    fn test_spend_allowance_exceeded() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        grant_allowance(&mut context, &mut contract);
+        spend(&mut context, &mut contract, 30);
+        spend(&mut context, &mut contract, 30);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_AMOUNT")]
+    fn test_spend_allowance_zero() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        grant_allowance(&mut context, &mut contract);
+        spend(&mut context, &mut contract, 0);
+    }
+
+    #[test]
+    fn test_spend_allowance_no_storage_deposit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().ft_storage_deposit = Some(NearToken::from_millinear(10));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        grant_allowance(&mut context, &mut contract);
+        spend(&mut context, &mut contract, 10);
+        // Receiver's storage isn't paid from the treasury, the tokens are transferred right away.
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts[0].receiver_id, accounts(4));
+        assert!(matches!(
+            &receipts[0].actions[0],
+            near_sdk::mock::MockAction::FunctionCallWeight { method_name, .. }
+                if method_name == b"ft_transfer"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_ALLOWANCE")]
+    fn test_revoke_allowance() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = grant_allowance(&mut context, &mut contract);
+        execute(
+            &mut context,
+            &mut contract,
+            ProposalKind::RevokeAllowance { allowance_id: id },
+        );
+        spend(&mut context, &mut contract, 10);
+    }
+}
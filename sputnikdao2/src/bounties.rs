@@ -169,6 +169,7 @@ impl Contract {
                     bounty_id: id,
                     receiver_id: sender_id.clone(),
                 },
+                depends_on: vec![],
             });
             claims[claim_idx].completed = true;
             self.bounty_claimers.insert(&sender_id, &claims);
@@ -220,6 +221,7 @@ mod tests {
                     max_deadline: U64::from(1_000),
                 },
             },
+            depends_on: vec![],
         });
         assert_eq!(contract.get_last_bounty_id(), id);
         contract.act_proposal(
@@ -314,6 +316,31 @@ mod tests {
         assert_eq!(contract.get_bounty(0).bounty.times, 0);
     }
 
+    #[test]
+    fn test_bounty_done_vetoed() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().roles[1]
+            .permissions
+            .insert("bounty_done:Veto".to_string());
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = add_bounty(&mut context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_done(id, None, "Bounty is done".to_string());
+        assert_eq!(contract.get_bounty_number_of_claims(id), 1);
+
+        contract.act_proposal(
+            1,
+            Action::Veto,
+            contract.get_proposal(1).proposal.kind,
+            None,
+        );
+        // Claim is released, so the bounty can be claimed again.
+        assert_eq!(contract.get_bounty_claims(accounts(1)).len(), 0);
+        assert_eq!(contract.get_bounty_number_of_claims(id), 0);
+    }
+
     #[test]
     #[should_panic(expected = "ERR_BOUNTY_ALL_CLAIMED")]
     fn test_bounty_claim_not_allowed() {
@@ -1,9 +1,83 @@
+use near_sdk::log;
+
+use crate::policy::WeightKind;
 use crate::*;
 
+/// Progress of recomputing the sum of the square roots of delegations after the migration,
+/// as delegations made before it can't be iterated.
+#[near(serializers=[borsh])]
+#[derive(Default)]
+pub struct QuadraticDelegationRecompute {
+    /// Current round of the recompute. Accounts counted in `quadratic_recompute_rounds` with
+    /// an earlier round are counted again, so restarting doesn't need to clear that map.
+    pub round: u32,
+    /// Sum of delegations of the counted accounts.
+    pub amount: Balance,
+    /// Sum of the square roots of delegations of the counted accounts.
+    pub quadratic_amount: Balance,
+}
+
+impl QuadraticDelegationRecompute {
+    /// Returns empty recompute in the next round.
+    fn restart(&self) -> Self {
+        Self {
+            round: self.round + 1,
+            amount: 0,
+            quadratic_amount: 0,
+        }
+    }
+}
+
 impl Contract {
     pub fn get_user_weight(&self, account_id: &AccountId) -> Balance {
         self.delegations.get(account_id).unwrap_or_default()
     }
+
+    /// Updates sum of the square roots of delegations once delegation of an account has changed.
+    fn internal_update_quadratic_delegation(
+        &mut self,
+        account_id: &AccountId,
+        prev_amount: Balance,
+        new_amount: Balance,
+    ) {
+        self.total_quadratic_delegation_amount = self
+            .total_quadratic_delegation_amount
+            .saturating_sub(prev_amount.isqrt())
+            + new_amount.isqrt();
+        // Account that has already been counted by the recompute is counted with its new amount.
+        if let Some(recompute) = &mut self.quadratic_delegation_recompute {
+            if self.quadratic_recompute_rounds.get(account_id) == Some(recompute.round) {
+                match (
+                    recompute.amount.checked_sub(prev_amount),
+                    recompute.quadratic_amount.checked_sub(prev_amount.isqrt()),
+                ) {
+                    (Some(amount), Some(quadratic_amount)) => {
+                        recompute.amount = amount + new_amount;
+                        recompute.quadratic_amount = quadratic_amount + new_amount.isqrt();
+                    }
+                    // Counted sums don't include this account, so they can't be trusted anymore.
+                    _ => {
+                        *recompute = recompute.restart();
+                        log!("Quadratic delegation recompute restarted");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Panics if given vote policies use quadratic weight before the sum of the square roots
+    /// of delegations has been recomputed after the migration.
+    pub(crate) fn internal_assert_quadratic_weight_available<'a>(
+        &self,
+        mut vote_policies: impl Iterator<Item = &'a VotePolicy>,
+    ) {
+        assert!(
+            self.quadratic_delegation_recompute.is_none()
+                || !vote_policies
+                    .any(|vote_policy| vote_policy.weight_kind == WeightKind::QuadraticTokenWeight),
+            "ERR_QUADRATIC_TOTAL_NOT_RECOMPUTED"
+        );
+    }
 }
 
 #[near]
@@ -39,6 +113,7 @@ impl Contract {
         let new_amount = prev_amount + amount.0;
         self.delegations.insert(account_id, &new_amount);
         self.total_delegation_amount += amount.0;
+        self.internal_update_quadratic_delegation(account_id, prev_amount, new_amount);
         (
             U128(prev_amount),
             U128(new_amount),
@@ -60,10 +135,53 @@ impl Contract {
         let new_amount = prev_amount - amount.0;
         self.delegations.insert(account_id, &new_amount);
         self.total_delegation_amount -= amount.0;
+        self.internal_update_quadratic_delegation(account_id, prev_amount, new_amount);
         (
             U128(prev_amount),
             U128(new_amount),
             self.delegation_total_supply(),
         )
     }
+
+    /// Recomputes the sum of the square roots of delegations made before the migration, page by page.
+    /// Can be called by anyone with delegated accounts in any order: amounts are read from the
+    /// delegations, and accounts already counted or without delegation are skipped.
+    /// Returns true once delegations of the counted accounts sum up to the total delegated amount,
+    /// i.e. every account with delegation has been counted, then quadratic weight can be used.
+    pub fn recompute_quadratic_delegation(&mut self, account_ids: Vec<AccountId>) -> bool {
+        let mut recompute = self
+            .quadratic_delegation_recompute
+            .take()
+            .expect("ERR_NOTHING_TO_RECOMPUTE");
+        for account_id in account_ids {
+            let amount = self.get_user_weight(&account_id);
+            if amount == 0
+                || self.quadratic_recompute_rounds.get(&account_id) == Some(recompute.round)
+            {
+                continue;
+            }
+            self.quadratic_recompute_rounds
+                .insert(&account_id, &recompute.round);
+            recompute.amount += amount;
+            recompute.quadratic_amount += amount.isqrt();
+        }
+        if recompute.amount == self.total_delegation_amount {
+            self.total_quadratic_delegation_amount = recompute.quadratic_amount;
+            true
+        } else {
+            self.quadratic_delegation_recompute = Some(recompute);
+            false
+        }
+    }
+
+    /// Restarts recomputing the sum of the square roots of delegations from scratch,
+    /// in case the counted sums got out of sync with the delegations.
+    #[private]
+    pub fn restart_quadratic_delegation_recompute(&mut self) {
+        let recompute = self
+            .quadratic_delegation_recompute
+            .as_ref()
+            .expect("ERR_NOTHING_TO_RECOMPUTE");
+        self.quadratic_delegation_recompute = Some(recompute.restart());
+    }
 }
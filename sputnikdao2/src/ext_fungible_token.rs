@@ -1,3 +1,4 @@
+use near_contract_standards::storage_management::StorageBalance;
 use near_sdk::{AccountId, PromiseOrValue, ext_contract, json_types::U128};
 
 #[allow(dead_code)]
@@ -11,4 +12,10 @@ pub trait FungibleToken {
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<U128>;
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance;
 }
@@ -1,8 +1,9 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use near_contract_standards::fungible_token::Balance;
+use near_contract_standards::non_fungible_token::TokenId;
 use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::{Base58CryptoHash, U128};
 use near_sdk::{
     AccountId, BorshStorageKey, CryptoHash, NearToken, PanicOnDefault, Promise, PromiseOrValue,
@@ -10,25 +11,42 @@ use near_sdk::{
 };
 
 use crate::action_log::ActionLog;
+pub use crate::allowances::Allowance;
 pub use crate::bounties::{Bounty, BountyClaim, VersionedBounty};
+use crate::delegation::QuadraticDelegationRecompute;
+use crate::policy::VersionedPolicyV1;
 pub use crate::policy::{
-    Policy, RoleKind, RolePermission, VersionedPolicy, VotePolicy, default_policy,
+    Policy, PolicyFinding, RoleKind, RolePermission, TokenBond, VersionedPolicy, VotePolicy,
+    default_policy,
 };
 use crate::proposals::VersionedProposal;
-pub use crate::proposals::{Proposal, ProposalInput, ProposalKind, ProposalStatus};
+pub use crate::proposals::{
+    Proposal, ProposalInput, ProposalKind, ProposalStatus, ProposalSummary,
+};
+pub use crate::recurring_payments::RecurringPayment;
+pub use crate::staking_pools::PoolBalance;
 pub use crate::types::{Action, Config, OLD_BASE_TOKEN, OldAccountId};
 use crate::upgrade::{
-    ContractV1, FactoryInfo, StateVersion, internal_get_factory_info, internal_set_factory_info,
-    state_version_read, state_version_write,
+    ContractV1, ContractV2, FactoryInfo, StateVersion, internal_get_factory_info,
+    internal_set_factory_info, state_version_read, state_version_write,
+};
+pub use crate::views::{
+    ActionSimulation, AllowanceOutput, BountyOutput, ProposalOutput, RecurringPaymentOutput,
+    RoleTally, TreasuryOutput,
 };
-pub use crate::views::{BountyOutput, ProposalOutput};
 
 pub mod action_log;
+mod allowances;
 mod bounties;
 mod delegation;
 mod ext_fungible_token;
+mod multi_tokens;
+mod non_fungible_tokens;
 mod policy;
 pub mod proposals;
+mod recurring_payments;
+mod staking_pools;
+mod sub_daos;
 mod types;
 mod upgrade;
 pub mod views;
@@ -44,6 +62,16 @@ pub enum StorageKeys {
     BountyClaimers,
     BountyClaimCounts,
     Blobs,
+    RecurringPayments,
+    PrunedProposals,
+    NftHoldings,
+    Allowances,
+    AllowanceIds,
+    TokenBalances,
+    PoolBalances,
+    NftStorageBalances,
+    SubDaos,
+    QuadraticRecomputeRounds,
 }
 
 /// After payouts, allows a callback
@@ -53,6 +81,13 @@ pub trait ExtSelf {
     fn on_proposal_callback(&mut self, proposal_id: u64) -> PromiseOrValue<()>;
 }
 
+/// Interface of another DAO, e.g. the hub that proposals are moved to.
+#[ext_contract(ext_dao)]
+pub trait ExtDao {
+    fn add_proposal(&mut self, proposal: ProposalInput) -> u64;
+    fn get_policy(&self) -> Policy;
+}
+
 #[near(contract_state)]
 #[derive(PanicOnDefault)]
 pub struct Contract {
@@ -63,6 +98,8 @@ pub struct Contract {
 
     /// Amount of $NEAR locked for bonds.
     pub locked_amount: NearToken,
+    /// Amount of fungible tokens locked for bonds per token.
+    pub locked_token_amounts: HashMap<AccountId, Balance>,
 
     /// Vote staking contract id. That contract must have this account as owner.
     pub staking_id: Option<AccountId>,
@@ -75,6 +112,8 @@ pub struct Contract {
     pub last_proposal_id: u64,
     /// Proposal map from ID to proposal information.
     pub proposals: LookupMap<u64, VersionedProposal>,
+    /// Summaries of the pruned proposals, that were removed from `proposals`.
+    pub pruned_proposals: LookupMap<u64, ProposalSummary>,
 
     /// Last available id for the bounty.
     pub last_bounty_id: u64,
@@ -90,6 +129,38 @@ pub struct Contract {
 
     /// Log of the latest actions on proposals
     pub actions_log: VecDeque<ActionLog>,
+
+    /// Last available id for the recurring payments.
+    pub last_recurring_payment_id: u64,
+    /// Recurring payment schedules map from ID to schedule information.
+    pub recurring_payments: LookupMap<u64, RecurringPayment>,
+
+    /// Amounts of $NEAR in the validator staking pools, as tracked by the DAO.
+    pub pool_balances: UnorderedMap<AccountId, PoolBalance>,
+
+    /// Sub-DAOs created by this DAO through the factory.
+    pub sub_daos: UnorderedSet<AccountId>,
+
+    /// Ids of the NFTs held by this DAO per NFT contract.
+    pub nft_holdings: LookupMap<AccountId, HashSet<TokenId>>,
+
+    /// Last available id for the allowances.
+    pub last_allowance_id: u64,
+    /// Spending allowances map from ID to allowance information.
+    pub allowances: LookupMap<u64, Allowance>,
+    /// Allowance id per spender and token.
+    pub allowance_ids: LookupMap<(AccountId, OldAccountId), u64>,
+    /// Amounts of fungible tokens in the treasury, deposited with `ft_transfer_call`.
+    pub token_balances: UnorderedMap<AccountId, Balance>,
+    /// Sum of the square roots of all delegations, total weight for quadratic voting.
+    pub total_quadratic_delegation_amount: Balance,
+    /// Progress of recomputing `total_quadratic_delegation_amount` after the migration.
+    /// Quadratic weight can't be used until it's done.
+    pub quadratic_delegation_recompute: Option<QuadraticDelegationRecompute>,
+    /// Round of the recompute in which each account was counted, so it's counted only once.
+    pub quadratic_recompute_rounds: LookupMap<AccountId, u32>,
+    /// Deposits for the storage of the received NFTs per NFT contract, see `nft_storage_deposit`.
+    pub nft_storage_balances: LookupMap<AccountId, NearToken>,
 }
 
 #[near]
@@ -104,19 +175,34 @@ impl Contract {
             delegations: LookupMap::new(StorageKeys::Delegations),
             last_proposal_id: 0,
             proposals: LookupMap::new(StorageKeys::Proposals),
+            pruned_proposals: LookupMap::new(StorageKeys::PrunedProposals),
             last_bounty_id: 0,
             bounties: LookupMap::new(StorageKeys::Bounties),
             bounty_claimers: LookupMap::new(StorageKeys::BountyClaimers),
             bounty_claims_count: LookupMap::new(StorageKeys::BountyClaimCounts),
             blobs: LookupMap::new(StorageKeys::Blobs),
             locked_amount: NearToken::from_near(0),
+            locked_token_amounts: HashMap::new(),
             actions_log: VecDeque::new(),
+            last_recurring_payment_id: 0,
+            recurring_payments: LookupMap::new(StorageKeys::RecurringPayments),
+            pool_balances: UnorderedMap::new(StorageKeys::PoolBalances),
+            sub_daos: UnorderedSet::new(StorageKeys::SubDaos),
+            nft_holdings: LookupMap::new(StorageKeys::NftHoldings),
+            last_allowance_id: 0,
+            allowances: LookupMap::new(StorageKeys::Allowances),
+            allowance_ids: LookupMap::new(StorageKeys::AllowanceIds),
+            token_balances: UnorderedMap::new(StorageKeys::TokenBalances),
+            total_quadratic_delegation_amount: 0,
+            quadratic_delegation_recompute: None,
+            quadratic_recompute_rounds: LookupMap::new(StorageKeys::QuadraticRecomputeRounds),
+            nft_storage_balances: LookupMap::new(StorageKeys::NftStorageBalances),
         };
         internal_set_factory_info(&FactoryInfo {
             factory_id: env::predecessor_account_id(),
             auto_update: true,
         });
-        state_version_write(&StateVersion::V2);
+        state_version_write(&StateVersion::V3);
         this
     }
 
@@ -128,29 +214,93 @@ impl Contract {
     #[init(ignore_state)]
     pub fn migrate() -> Self {
         let version = state_version_read();
-        match version {
+        let mut contract = match version {
             StateVersion::V1 => {
                 let this: ContractV1 = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
-                state_version_write(&StateVersion::V2);
                 Contract {
                     config: this.config,
                     policy: this.policy,
                     locked_amount: this.locked_amount,
+                    locked_token_amounts: HashMap::new(),
                     staking_id: this.staking_id,
                     total_delegation_amount: this.total_delegation_amount,
                     delegations: this.delegations,
                     last_proposal_id: this.last_proposal_id,
                     proposals: this.proposals,
+                    pruned_proposals: LookupMap::new(StorageKeys::PrunedProposals),
                     last_bounty_id: this.last_bounty_id,
                     bounties: this.bounties,
                     bounty_claimers: this.bounty_claimers,
                     bounty_claims_count: this.bounty_claims_count,
                     blobs: this.blobs,
                     actions_log: VecDeque::new(),
+                    last_recurring_payment_id: 0,
+                    recurring_payments: LookupMap::new(StorageKeys::RecurringPayments),
+                    pool_balances: UnorderedMap::new(StorageKeys::PoolBalances),
+                    sub_daos: UnorderedSet::new(StorageKeys::SubDaos),
+                    nft_holdings: LookupMap::new(StorageKeys::NftHoldings),
+                    last_allowance_id: 0,
+                    allowances: LookupMap::new(StorageKeys::Allowances),
+                    allowance_ids: LookupMap::new(StorageKeys::AllowanceIds),
+                    token_balances: UnorderedMap::new(StorageKeys::TokenBalances),
+                    // Delegations can't be iterated, the sum is recomputed with `recompute_quadratic_delegation`.
+                    total_quadratic_delegation_amount: 0,
+                    quadratic_delegation_recompute: (this.total_delegation_amount > 0)
+                        .then(QuadraticDelegationRecompute::default),
+                    quadratic_recompute_rounds: LookupMap::new(
+                        StorageKeys::QuadraticRecomputeRounds,
+                    ),
+                    nft_storage_balances: LookupMap::new(StorageKeys::NftStorageBalances),
                 }
             }
-            StateVersion::V2 => env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED"),
-        }
+            StateVersion::V2 => {
+                let this: ContractV2 = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
+                Contract {
+                    config: this.config,
+                    policy: this.policy,
+                    locked_amount: this.locked_amount,
+                    locked_token_amounts: HashMap::new(),
+                    staking_id: this.staking_id,
+                    total_delegation_amount: this.total_delegation_amount,
+                    delegations: this.delegations,
+                    last_proposal_id: this.last_proposal_id,
+                    proposals: this.proposals,
+                    pruned_proposals: LookupMap::new(StorageKeys::PrunedProposals),
+                    last_bounty_id: this.last_bounty_id,
+                    bounties: this.bounties,
+                    bounty_claimers: this.bounty_claimers,
+                    bounty_claims_count: this.bounty_claims_count,
+                    blobs: this.blobs,
+                    actions_log: this.actions_log,
+                    last_recurring_payment_id: 0,
+                    recurring_payments: LookupMap::new(StorageKeys::RecurringPayments),
+                    pool_balances: UnorderedMap::new(StorageKeys::PoolBalances),
+                    sub_daos: UnorderedSet::new(StorageKeys::SubDaos),
+                    nft_holdings: LookupMap::new(StorageKeys::NftHoldings),
+                    last_allowance_id: 0,
+                    allowances: LookupMap::new(StorageKeys::Allowances),
+                    allowance_ids: LookupMap::new(StorageKeys::AllowanceIds),
+                    token_balances: UnorderedMap::new(StorageKeys::TokenBalances),
+                    // Delegations can't be iterated, the sum is recomputed with `recompute_quadratic_delegation`.
+                    total_quadratic_delegation_amount: 0,
+                    quadratic_delegation_recompute: (this.total_delegation_amount > 0)
+                        .then(QuadraticDelegationRecompute::default),
+                    quadratic_recompute_rounds: LookupMap::new(
+                        StorageKeys::QuadraticRecomputeRounds,
+                    ),
+                    nft_storage_balances: LookupMap::new(StorageKeys::NftStorageBalances),
+                }
+            }
+            StateVersion::V3 => {
+                return env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
+            }
+        };
+        // Policy layout has changed in V3: it now includes execution delays, overrides per proposal kind and bond token.
+        let old_policy: LazyOption<VersionedPolicyV1> = LazyOption::new(StorageKeys::Policy, None);
+        let policy: VersionedPolicy = old_policy.get().expect("ERR_NO_POLICY").into();
+        contract.policy.set(&policy);
+        state_version_write(&StateVersion::V3);
+        contract
     }
 
     /// Remove blob from contract storage and pay back to original storer.
@@ -210,12 +360,20 @@ pub extern "C" fn store_blob() {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use near_api::types::NearToken;
+    use near_sdk::json_types::U64;
     use near_sdk::test_utils::{VMContextBuilder, accounts};
-    use near_sdk::testing_env;
+    use near_sdk::{PromiseResult, testing_env};
+
+    use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 
-    use crate::action_log::ProposalLog;
+    use crate::action_log::{LoggedAction, ProposalLog};
+    use crate::policy::{PolicyV1, WeightKind};
     use crate::proposals::ProposalStatus;
+    use crate::proposals::RemoteProposal;
+    use crate::proposals::{PolicyParametersV1, ProposalKindV1, ProposalV2};
 
     use super::*;
 
@@ -229,9 +387,31 @@ mod tests {
                 amount: U128(NearToken::from_near(100).as_yoctonear()),
                 msg: None,
             },
+            depends_on: vec![],
         })
     }
 
+    /// Adds proposal of given kind and approves it with a single vote.
+    pub(crate) fn add_approved_proposal(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        kind: ProposalKind,
+    ) -> u64 {
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind,
+            depends_on: vec![],
+        });
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        id
+    }
+
     #[test]
     fn test_basics() {
         let mut context = VMContextBuilder::new();
@@ -287,6 +467,7 @@ mod tests {
                 member_id: accounts(2),
                 role: "council".to_string(),
             },
+            depends_on: vec![],
         });
     }
 
@@ -329,37 +510,120 @@ mod tests {
         assert_eq!(contract.get_proposals(0, 10).len(), 0);
     }
 
+    fn policy_with_transfer_delay() -> VersionedPolicy {
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().execution_delay.insert(
+            "transfer".to_string(),
+            U64::from(1_000_000_000 * 60 * 60 * 24),
+        );
+        policy
+    }
+
     #[test]
-    fn test_vote_expired_proposal() {
+    fn test_execution_delay() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
-        let mut contract = Contract::new(
-            Config::test_config(),
-            VersionedPolicy::Default(vec![accounts(1)]),
-        );
+        let mut contract = Contract::new(Config::test_config(), policy_with_transfer_delay());
         let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Queued
+        );
+
+        // Anyone can execute after the delay has passed.
         testing_env!(
             context
-                .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+                .predecessor_account_id(accounts(3))
+                .block_timestamp(1_000_000_000 * 60 * 60 * 24)
                 .build()
         );
+        contract.act_proposal(
+            id,
+            Action::Execute,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_EXECUTION_DELAY_NOT_PASSED")]
+    fn test_execute_before_delay() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(Config::test_config(), policy_with_transfer_delay());
+        let id = create_proposal(&mut context, &mut contract);
         contract.act_proposal(
             id,
             Action::VoteApprove,
             contract.get_proposal(id).proposal.kind,
             None,
         );
+        contract.act_proposal(
+            id,
+            Action::Execute,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
     }
 
     #[test]
-    #[should_panic(expected = "ERR_ALREADY_VOTED")]
-    fn test_vote_twice() {
+    #[should_panic(expected = "ERR_EXECUTION_DELAY_NOT_PASSED")]
+    fn test_non_member_execute_before_delay() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
-        let mut contract = Contract::new(
-            Config::test_config(),
-            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        let mut contract = Contract::new(Config::test_config(), policy_with_transfer_delay());
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        // Non-members have no `Execute` permission, but are only held back by the delay.
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        assert!(
+            !contract
+                .policy
+                .get()
+                .unwrap()
+                .to_policy()
+                .can_execute_action(
+                    contract.internal_user_info(),
+                    &contract.get_proposal(id).proposal.kind,
+                    &Action::Execute,
+                )
+                .1
+        );
+        contract.act_proposal(
+            id,
+            Action::Execute,
+            contract.get_proposal(id).proposal.kind,
+            None,
         );
+    }
+
+    #[test]
+    fn test_cancel_queued_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = policy_with_transfer_delay();
+        policy.to_policy_mut().roles.push(RolePermission {
+            name: "guardian".to_string(),
+            kind: RoleKind::Group(vec![accounts(2)].into_iter().collect()),
+            permissions: vec!["*:Cancel".to_string()].into_iter().collect(),
+            vote_policy: HashMap::default(),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
         let id = create_proposal(&mut context, &mut contract);
         contract.act_proposal(
             id,
@@ -367,39 +631,193 @@ mod tests {
             contract.get_proposal(id).proposal.kind,
             None,
         );
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(
+            id,
+            Action::Cancel,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Rejected
+        );
+    }
+
+    #[test]
+    fn test_batch_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut config = Config::test_config();
+        config.name = "Batch".to_string();
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Batch {
+                kinds: vec![
+                    ProposalKind::ChangeConfig {
+                        config: config.clone(),
+                    },
+                    ProposalKind::AddMemberToRole {
+                        member_id: accounts(2),
+                        role: "council".to_string(),
+                    },
+                    ProposalKind::AddMemberToRole {
+                        member_id: accounts(3),
+                        role: "council".to_string(),
+                    },
+                ],
+            },
+            depends_on: vec![],
+        });
         contract.act_proposal(
             id,
             Action::VoteApprove,
             contract.get_proposal(id).proposal.kind,
             None,
         );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(contract.get_config(), config);
+        // Both members are added, as each kind sees the policy changed by the previous one.
+        assert_eq!(contract.get_policy().roles[1].kind.get_role_size(), Some(3));
     }
 
     #[test]
-    #[should_panic(expected = "ERR_WRONG_KIND")]
-    fn test_wrong_kind() {
+    fn test_batch_proposal_failed_promise() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
         let mut contract = Contract::new(
             Config::test_config(),
-            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+            VersionedPolicy::Default(vec![accounts(1)]),
         );
-        let id = create_proposal(&mut context, &mut contract);
+        let transfer = |receiver_id| ProposalKind::Transfer {
+            token_id: String::from(OLD_BASE_TOKEN),
+            receiver_id,
+            amount: U128(NearToken::from_near(1).as_yoctonear()),
+            msg: None,
+        };
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Batch {
+                kinds: vec![transfer(accounts(2)), transfer(accounts(3))],
+            },
+            depends_on: vec![],
+        });
         contract.act_proposal(
             id,
             Action::VoteApprove,
-            ProposalKind::Transfer {
-                token_id: String::from(OLD_BASE_TOKEN),
-                receiver_id: accounts(1), // The only different thing from initial kind
-                amount: U128(NearToken::from_near(100).as_yoctonear()),
-                msg: None,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed],
+        );
+        assert!(matches!(
+            contract.on_proposal_callback(id),
+            PromiseOrValue::Value(())
+        ));
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Failed);
+        let failure = proposal.failure.unwrap();
+        assert_eq!(failure.count, 1);
+        assert_eq!(failure.failed_promises, vec![1]);
+    }
+
+    #[test]
+    fn test_batch_proposal_retries_failed_kinds() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        for token_id in [accounts(4), accounts(5)] {
+            deposit_tokens(&mut context, &mut contract, token_id, 100);
+        }
+        let transfer = |token_id: AccountId| ProposalKind::Transfer {
+            token_id: token_id.to_string(),
+            receiver_id: accounts(3),
+            amount: U128(10),
+            msg: None,
+        };
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(1))
+                .attached_deposit(NearToken::from_near(1))
+                .build()
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Batch {
+                kinds: vec![
+                    ProposalKind::ChangeConfig {
+                        config: Config::test_config(),
+                    },
+                    transfer(accounts(4)),
+                    transfer(accounts(5)),
+                ],
             },
+            depends_on: vec![],
+        });
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
             None,
         );
+        assert_eq!(contract.get_proposal(id).proposal.completed_kinds, vec![0]);
+
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed],
+        );
+        let _ = contract.on_proposal_callback(id);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Failed);
+        assert_eq!(proposal.completed_kinds, vec![0, 1]);
+
+        // Only the failed kind is executed again.
+        testing_env!(context.build());
+        contract.act_proposal(id, Action::Finalize, proposal.kind, None);
+        let receipts = near_sdk::test_utils::get_created_receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].receiver_id, accounts(5));
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let _ = contract.on_proposal_callback(id);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        let tokens = contract.get_treasury(0, 10).tokens;
+        assert_eq!(tokens[&accounts(4)], U128(90));
+        assert_eq!(tokens[&accounts(5)], U128(90));
     }
 
     #[test]
-    fn test_add_to_missing_role() {
+    #[should_panic(expected = "ERR_INVALID_BATCH_KIND")]
+    fn test_batch_proposal_upgrade_self() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
         let mut contract = Contract::new(
@@ -407,27 +825,78 @@ mod tests {
             VersionedPolicy::Default(vec![accounts(1)]),
         );
         testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
-        let id = contract.add_proposal(ProposalInput {
+        contract.add_proposal(ProposalInput {
             description: "test".to_string(),
-            kind: ProposalKind::AddMemberToRole {
-                member_id: accounts(2),
-                role: "missing".to_string(),
+            kind: ProposalKind::Batch {
+                kinds: vec![ProposalKind::UpgradeSelf {
+                    hash: Base58CryptoHash::from([0; 32]),
+                }],
             },
+            depends_on: vec![],
         });
+    }
+
+    #[test]
+    fn test_failed_proposal_max_retries() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().failed_proposal_max_retries = Some(1);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
         contract.act_proposal(
             id,
             Action::VoteApprove,
             contract.get_proposal(id).proposal.kind,
             None,
         );
-        let x = contract.get_policy();
-        // still 2 roles: all and council.
-        assert_eq!(x.roles.len(), 2);
+        let fail = |contract: &mut Contract, height| {
+            testing_env!(
+                VMContextBuilder::new()
+                    .predecessor_account_id(accounts(1))
+                    .block_height(height)
+                    .build(),
+                near_sdk::test_vm_config(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                vec![PromiseResult::Failed],
+            );
+            let _ = contract.on_proposal_callback(id);
+        };
+
+        fail(&mut contract, 10);
+        // Can be retried once.
+        contract.act_proposal(
+            id,
+            Action::Finalize,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+
+        fail(&mut contract, 20);
+        let failure = contract.get_proposal(id).proposal.failure.unwrap();
+        assert_eq!(failure.block_height, U64(20));
+        assert_eq!(failure.count, 2);
+        contract.act_proposal(
+            id,
+            Action::Finalize,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Expired
+        );
+        assert_eq!(contract.locked_amount, NearToken::from_near(0));
     }
 
     #[test]
-    #[should_panic(expected = "ERR_INVALID_POLICY")]
-    fn test_fails_adding_invalid_policy() {
+    #[should_panic(expected = "ERR_INVALID_BATCH_KIND")]
+    fn test_nested_batch_proposal() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
         let mut contract = Contract::new(
@@ -435,14 +904,1506 @@ mod tests {
             VersionedPolicy::Default(vec![accounts(1)]),
         );
         testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
-        let _id = contract.add_proposal(ProposalInput {
+        contract.add_proposal(ProposalInput {
             description: "test".to_string(),
-            kind: ProposalKind::ChangePolicy {
-                policy: VersionedPolicy::Default(vec![]),
+            kind: ProposalKind::Batch {
+                kinds: vec![ProposalKind::Batch {
+                    kinds: vec![ProposalKind::Vote],
+                }],
             },
+            depends_on: vec![],
         });
     }
 
+    #[test]
+    #[should_panic(expected = "ERR_CALL_CHAIN_STEP_FAILED")]
+    fn test_call_chain_step_failed() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let steps = near_sdk::serde_json::from_value(near_sdk::serde_json::json!([{
+            "receiver_id": accounts(2),
+            "actions": [{"method_name": "mint", "args": "", "deposit": "0", "gas": "10000000000000"}]
+        }]))
+        .unwrap();
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_call_chain_step_callback(steps).detach();
+    }
+
+    #[test]
+    fn test_withdraw_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(contract.locked_amount, NearToken::from_near(1));
+        contract.withdraw_proposal(id);
+        assert_eq!(contract.get_proposals(0, 10).len(), 0);
+        assert_eq!(contract.locked_amount, NearToken::from_near(0));
+        assert_eq!(
+            contract.get_actions_log().back().unwrap().action,
+            LoggedAction::WithdrawProposal
+        );
+    }
+
+    #[test]
+    fn test_logged_action_borsh_matches_action() {
+        for action in [Action::AddProposal, Action::MoveToHub, Action::Veto] {
+            assert_eq!(
+                near_sdk::borsh::to_vec(&LoggedAction::from(action.clone())).unwrap(),
+                near_sdk::borsh::to_vec(&action).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_amend_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.amend_proposal(id, "amended".to_string(), ProposalKind::Vote);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.description, "amended");
+        assert_eq!(proposal.kind, ProposalKind::Vote);
+        assert_eq!(proposal.last_actions_log.len(), 2);
+        assert_eq!(
+            contract.get_actions_log().back().unwrap(),
+            &ActionLog {
+                account_id: accounts(1),
+                proposal_id: id.into(),
+                action: LoggedAction::AmendProposal,
+                block_height: 0.into()
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_HAS_VOTES")]
+    fn test_amend_proposal_after_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        contract.amend_proposal(id, "amended".to_string(), ProposalKind::Vote);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_CALLER")]
+    fn test_withdraw_proposal_not_proposer() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.withdraw_proposal(id);
+    }
+
+    fn policy_with_veto_role() -> VersionedPolicy {
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().roles.push(RolePermission {
+            name: "security_council".to_string(),
+            kind: RoleKind::Group(vec![accounts(3)].into_iter().collect()),
+            permissions: vec!["*:Veto".to_string()].into_iter().collect(),
+            vote_policy: HashMap::default(),
+        });
+        policy
+    }
+
+    #[test]
+    fn test_veto_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(Config::test_config(), policy_with_veto_role());
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(
+            id,
+            Action::Veto,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Vetoed
+        );
+        assert_eq!(contract.locked_amount, NearToken::from_near(0));
+        assert_eq!(
+            contract.get_actions_log().back().unwrap(),
+            &ActionLog {
+                account_id: accounts(3),
+                proposal_id: id.into(),
+                action: LoggedAction::Veto,
+                block_height: 0.into()
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_veto_role_cant_approve() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(Config::test_config(), policy_with_veto_role());
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_early_reject() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3), accounts(4)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        // 3 out of 4 votes are needed, so after 2 rejections approval is not possible.
+        contract.act_proposal(
+            id,
+            Action::VoteReject,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(
+            id,
+            Action::VoteReject,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Rejected
+        );
+        assert_eq!(contract.locked_amount, NearToken::from_near(0));
+    }
+
+    #[test]
+    fn test_finalize_early_reject() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let council = vec![
+            accounts(0),
+            accounts(1),
+            accounts(2),
+            accounts(3),
+            accounts(4),
+        ];
+        let mut contract = Contract::new(Config::test_config(), VersionedPolicy::Default(council));
+        let id = create_proposal(&mut context, &mut contract);
+        for account in [accounts(1), accounts(2)] {
+            testing_env!(context.predecessor_account_id(account).build());
+            contract.act_proposal(
+                id,
+                Action::VoteReject,
+                contract.get_proposal(id).proposal.kind,
+                None,
+            );
+        }
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+
+        // Council shrinks to 4 members, so the first proposal can't be approved anymore.
+        let remove_id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::RemoveMemberFromRole {
+                member_id: accounts(4),
+                role: "council".to_string(),
+            },
+            depends_on: vec![],
+        });
+        for account in [accounts(0), accounts(3), accounts(4)] {
+            testing_env!(context.predecessor_account_id(account).build());
+            contract.act_proposal(
+                remove_id,
+                Action::VoteApprove,
+                contract.get_proposal(remove_id).proposal.kind,
+                None,
+            );
+        }
+        assert_eq!(
+            contract.get_proposal(remove_id).proposal.status,
+            ProposalStatus::Approved
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.act_proposal(
+            id,
+            Action::Finalize,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Rejected
+        );
+    }
+
+    #[test]
+    fn test_proposal_kind_overrides() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy
+            .to_policy_mut()
+            .proposal_bond_overrides
+            .insert("vote".to_string(), NearToken::from_millinear(100));
+        policy
+            .to_policy_mut()
+            .proposal_period_overrides
+            .insert("vote".to_string(), U64(1_000_000_000 * 60 * 60));
+        let mut contract = Contract::new(Config::test_config(), policy);
+
+        testing_env!(
+            context
+                .attached_deposit(NearToken::from_millinear(100))
+                .build()
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            depends_on: vec![],
+        });
+        assert_eq!(contract.locked_amount, NearToken::from_millinear(100));
+        // Other kinds still use the default period.
+        let transfer_id = create_proposal(&mut context, &mut contract);
+
+        testing_env!(context.block_timestamp(1_000_000_000 * 60 * 60 * 2).build());
+        contract.act_proposal(
+            id,
+            Action::Finalize,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Expired
+        );
+        assert_eq!(contract.locked_amount, NearToken::from_near(1));
+        contract.act_proposal(
+            transfer_id,
+            Action::VoteApprove,
+            contract.get_proposal(transfer_id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(transfer_id).proposal.status,
+            ProposalStatus::InProgress
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MIN_BOND")]
+    fn test_proposal_bond_override() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy
+            .to_policy_mut()
+            .proposal_bond_overrides
+            .insert("transfer".to_string(), NearToken::from_near(10));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        create_proposal(&mut context, &mut contract);
+    }
+
+    #[test]
+    fn test_proposal_token_bond() {
+        let mut context = VMContextBuilder::new();
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().proposal_bond_token = Some(TokenBond {
+            token_id: accounts(5),
+            amount: U128(10),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        let msg = near_sdk::serde_json::json!({
+            "description": "test",
+            "kind": "Vote",
+        });
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(10), msg.to_string()),
+            PromiseOrValue::Value(U128(0))
+        ));
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.proposer, accounts(2));
+        assert_eq!(contract.get_locked_token_amount(accounts(5)), U128(10));
+        assert_eq!(contract.locked_amount, NearToken::from_near(0));
+        assert_eq!(contract.get_actions_log()[0].account_id, accounts(2));
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.withdraw_proposal(0);
+        assert_eq!(contract.get_locked_token_amount(accounts(5)), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_WRONG_BOND_TOKEN")]
+    fn test_proposal_token_bond_wrong_token() {
+        let mut context = VMContextBuilder::new();
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().proposal_bond_token = Some(TokenBond {
+            token_id: accounts(5),
+            amount: U128(10),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        let msg = near_sdk::serde_json::json!({
+            "description": "test",
+            "kind": "Vote",
+        });
+        let _ = contract.ft_on_transfer(accounts(2), U128(10), msg.to_string());
+    }
+
+    /// Registers given token in the treasury and deposits given amount of it with `ft_transfer_call`.
+    fn deposit_tokens(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        token_id: AccountId,
+        amount: Balance,
+    ) {
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(2))
+                .attached_deposit(NearToken::from_millinear(10))
+                .build()
+        );
+        contract.register_treasury_token(token_id.clone());
+        testing_env!(
+            context
+                .predecessor_account_id(token_id)
+                .attached_deposit(NearToken::from_near(0))
+                .build()
+        );
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(amount), String::new()),
+            PromiseOrValue::Value(U128(0))
+        ));
+    }
+
+    #[test]
+    fn test_treasury_unregistered_token() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        // Tokens are kept, but not tracked, so the ledger can't be filled by anyone for free.
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        assert!(matches!(
+            contract.ft_on_transfer(accounts(2), U128(100), String::new()),
+            PromiseOrValue::Value(U128(0))
+        ));
+        assert!(contract.get_treasury(0, 10).tokens.is_empty());
+
+        deposit_tokens(&mut context, &mut contract, accounts(4), 10);
+        deposit_tokens(&mut context, &mut contract, accounts(5), 20);
+        assert_eq!(contract.get_treasury(0, 10).tokens.len(), 2);
+        assert_eq!(
+            contract.get_treasury(1, 10).tokens,
+            [(accounts(5), U128(20))].into_iter().collect()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_DEPOSIT")]
+    fn test_register_treasury_token_without_deposit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.register_treasury_token(accounts(5));
+    }
+
+    #[test]
+    fn test_treasury_token_balances() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        deposit_tokens(&mut context, &mut contract, accounts(5), 100);
+        assert_eq!(contract.get_treasury(0, 10).tokens[&accounts(5)], U128(100));
+
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(1))
+                .attached_deposit(NearToken::from_near(1))
+                .build()
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: accounts(5).to_string(),
+                receiver_id: accounts(2),
+                amount: U128(30),
+                msg: None,
+            },
+            depends_on: vec![],
+        });
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        // Nothing is debited until the transfer succeeds.
+        assert_eq!(contract.get_treasury(0, 10).tokens[&accounts(5)], U128(100));
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let _ = contract.on_proposal_callback(id);
+        assert_eq!(contract.get_treasury(0, 10).tokens[&accounts(5)], U128(70));
+
+        // Receiver of `ft_transfer_call` uses only a part of the amount, the rest is refunded.
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(1))
+                .attached_deposit(NearToken::from_near(1))
+                .build()
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: accounts(5).to_string(),
+                receiver_id: accounts(2),
+                amount: U128(30),
+                msg: Some("deposit".to_string()),
+            },
+            depends_on: vec![],
+        });
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"20\"".to_vec())],
+        );
+        let _ = contract.on_proposal_callback(id);
+        assert_eq!(contract.get_treasury(0, 10).tokens[&accounts(5)], U128(50));
+    }
+
+    /// Returns method names and deposits of the function calls scheduled so far.
+    fn created_function_calls() -> Vec<(String, NearToken)> {
+        near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| receipt.actions)
+            .filter_map(|action| match action {
+                near_sdk::mock::MockAction::FunctionCallWeight {
+                    method_name,
+                    attached_deposit,
+                    ..
+                } => Some((String::from_utf8(method_name).unwrap(), attached_deposit)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ft_payout_storage_registration() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().ft_storage_deposit = Some(NearToken::from_millinear(10));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let _ = contract.internal_payout(
+            &Some(accounts(5)),
+            &accounts(2),
+            10,
+            "test".to_string(),
+            None,
+        );
+        assert_eq!(
+            created_function_calls()
+                .into_iter()
+                .map(|(method_name, _)| method_name)
+                .collect::<Vec<_>>(),
+            vec!["storage_balance_of", "on_ft_storage_balance_callback"]
+        );
+
+        // Unregistered receiver is registered before the transfer.
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let _ = contract.on_ft_storage_balance_callback(
+            accounts(5),
+            accounts(2),
+            U128(10),
+            "test".to_string(),
+            None,
+            Ok(None),
+        );
+        assert_eq!(
+            created_function_calls(),
+            vec![
+                ("storage_deposit".to_string(), NearToken::from_millinear(10)),
+                ("ft_transfer".to_string(), NearToken::from_yoctonear(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_batch_transfer_retries_failed_payments() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        deposit_tokens(&mut context, &mut contract, accounts(5), 100);
+
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(1))
+                .attached_deposit(NearToken::from_near(1))
+                .build()
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::BatchTransfer {
+                token_id: accounts(5).to_string(),
+                payments: vec![
+                    (accounts(2), U128(10)),
+                    (accounts(3), U128(20)),
+                    (accounts(4), U128(30)),
+                ],
+            },
+            depends_on: vec![],
+        });
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(created_function_calls().len(), 4);
+
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![
+                PromiseResult::Successful(vec![]),
+                PromiseResult::Failed,
+                PromiseResult::Successful(vec![]),
+            ],
+        );
+        let _ = contract.on_proposal_callback(id);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Failed);
+        assert_eq!(proposal.completed_payments, vec![accounts(2), accounts(4)]);
+        assert_eq!(contract.get_treasury(0, 10).tokens[&accounts(5)], U128(60));
+
+        // Only the failed payment is retried.
+        testing_env!(context.build());
+        contract.act_proposal(id, Action::Finalize, proposal.kind, None);
+        assert_eq!(
+            created_function_calls()
+                .into_iter()
+                .map(|(method_name, _)| method_name)
+                .collect::<Vec<_>>(),
+            vec!["ft_transfer", "on_proposal_callback"]
+        );
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let _ = contract.on_proposal_callback(id);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(contract.get_treasury(0, 10).tokens[&accounts(5)], U128(40));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DUPLICATE_RECEIVER")]
+    fn test_batch_transfer_duplicate_receiver() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::BatchTransfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                payments: vec![(accounts(2), U128(10)), (accounts(2), U128(10))],
+            },
+            depends_on: vec![],
+        });
+    }
+
+    fn add_vote_proposal_depending_on(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        depends_on: Vec<u64>,
+    ) -> u64 {
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            depends_on,
+        })
+    }
+
+    #[test]
+    fn test_proposal_dependencies() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let dependency_id = create_proposal(&mut context, &mut contract);
+        let id = add_vote_proposal_depending_on(&mut context, &mut contract, vec![dependency_id]);
+
+        contract.act_proposal(id, Action::VoteApprove, ProposalKind::Vote, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Pending
+        );
+
+        contract.act_proposal(
+            dependency_id,
+            Action::VoteApprove,
+            contract.get_proposal(dependency_id).proposal.kind,
+            None,
+        );
+        assert!(!contract.get_proposal(dependency_id).proposal.executed);
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let _ = contract.on_proposal_callback(dependency_id);
+        assert!(contract.get_proposal(dependency_id).proposal.executed);
+
+        testing_env!(context.build());
+        contract.act_proposal(id, Action::Finalize, ProposalKind::Vote, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+        // Executed right away, as it doesn't need any promises.
+        assert!(proposal.executed);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DEPENDENCIES_NOT_EXECUTED")]
+    fn test_finalize_pending_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let dependency_id = create_proposal(&mut context, &mut contract);
+        let id = add_vote_proposal_depending_on(&mut context, &mut contract, vec![dependency_id]);
+        contract.act_proposal(id, Action::VoteApprove, ProposalKind::Vote, None);
+        contract.act_proposal(id, Action::Finalize, ProposalKind::Vote, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DEPENDENCIES_NOT_EXECUTED")]
+    fn test_finalize_dependency_execution_in_flight() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let dependency_id = create_proposal(&mut context, &mut contract);
+        let id = add_vote_proposal_depending_on(&mut context, &mut contract, vec![dependency_id]);
+        contract.act_proposal(id, Action::VoteApprove, ProposalKind::Vote, None);
+        // Dependency is approved, but its transfer hasn't been confirmed yet.
+        contract.act_proposal(
+            dependency_id,
+            Action::VoteApprove,
+            contract.get_proposal(dependency_id).proposal.kind,
+            None,
+        );
+        contract.act_proposal(id, Action::Finalize, ProposalKind::Vote, None);
+    }
+
+    #[test]
+    fn test_proposal_dependency_rejected() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let dependency_id = create_proposal(&mut context, &mut contract);
+        let id = add_vote_proposal_depending_on(&mut context, &mut contract, vec![dependency_id]);
+        contract.act_proposal(id, Action::VoteApprove, ProposalKind::Vote, None);
+
+        contract.act_proposal(
+            dependency_id,
+            Action::VoteReject,
+            contract.get_proposal(dependency_id).proposal.kind,
+            None,
+        );
+        contract.act_proposal(id, Action::Finalize, ProposalKind::Vote, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Expired
+        );
+        assert_eq!(contract.locked_amount, NearToken::from_near(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_DEPENDENCY")]
+    fn test_proposal_missing_dependency() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        add_vote_proposal_depending_on(&mut context, &mut contract, vec![0]);
+    }
+
+    fn contract_with_hub(context: &mut VMContextBuilder, hub_id: Option<AccountId>) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().roles[1]
+            .permissions
+            .insert("*:MoveToHub".to_string());
+        policy.to_policy_mut().hub_id = hub_id;
+        Contract::new(Config::test_config(), policy)
+    }
+
+    #[test]
+    fn test_move_to_hub() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_hub(&mut context, Some(accounts(4)));
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::MoveToHub,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Moved
+        );
+        assert_eq!(
+            created_function_calls(),
+            vec![
+                ("get_policy".to_string(), NearToken::from_near(0)),
+                (
+                    "on_hub_policy_callback".to_string(),
+                    NearToken::from_near(0)
+                ),
+            ]
+        );
+
+        // Hub bond is read from the hub policy and paid from the treasury,
+        // bond of the proposer stays locked until it's added.
+        let mut hub_policy = VersionedPolicy::Default(vec![accounts(4)]).upgrade();
+        hub_policy.to_policy_mut().proposal_bond = NearToken::from_near(2);
+        testing_env!(context.build());
+        let _ =
+            contract.on_hub_policy_callback(id, accounts(4), Ok(hub_policy.to_policy().clone()));
+        assert_eq!(
+            created_function_calls(),
+            vec![
+                ("add_proposal".to_string(), NearToken::from_near(2)),
+                (
+                    "on_move_to_hub_callback".to_string(),
+                    NearToken::from_near(0)
+                ),
+            ]
+        );
+        assert_eq!(contract.locked_amount, NearToken::from_near(1));
+
+        contract.on_move_to_hub_callback(id, accounts(4), Ok(7));
+        assert_eq!(contract.locked_amount, NearToken::from_near(0));
+        let refund = near_sdk::test_utils::get_created_receipts().pop().unwrap();
+        assert_eq!(refund.receiver_id, accounts(1));
+        assert!(matches!(
+            refund.actions[..],
+            [near_sdk::mock::MockAction::Transfer { deposit, .. }] if deposit == NearToken::from_near(1)
+        ));
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Moved);
+        assert_eq!(
+            proposal.moved_to,
+            Some(RemoteProposal {
+                dao_id: accounts(4),
+                proposal_id: 7
+            })
+        );
+    }
+
+    #[test]
+    fn test_move_to_hub_failed() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_hub(&mut context, Some(accounts(4)));
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::MoveToHub,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        contract.on_move_to_hub_callback(id, accounts(4), Err(near_sdk::PromiseError::Failed));
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        assert_eq!(proposal.moved_to, None);
+        assert_eq!(contract.locked_amount, NearToken::from_near(1));
+    }
+
+    #[test]
+    fn test_move_to_hub_policy_failed() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_hub(&mut context, Some(accounts(4)));
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::MoveToHub,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        testing_env!(context.build());
+        let _ =
+            contract.on_hub_policy_callback(id, accounts(4), Err(near_sdk::PromiseError::Failed));
+        assert!(created_function_calls().is_empty());
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+        assert_eq!(contract.locked_amount, NearToken::from_near(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_HUB")]
+    fn test_move_to_hub_without_hub() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_hub(&mut context, None);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::MoveToHub,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+    }
+
+    fn contract_with_retention_period(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().proposal_retention_period = Some(U64(1_000));
+        Contract::new(Config::test_config(), policy)
+    }
+
+    #[test]
+    fn test_prune_proposals() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_retention_period(&mut context);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(500).build());
+        contract.act_proposal(
+            id,
+            Action::VoteReject,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.finalized_at,
+            Some(U64(500))
+        );
+        let hash = env::sha256_array(
+            near_sdk::borsh::to_vec(&contract.proposals.get(&id).unwrap()).unwrap(),
+        );
+
+        // Retention period is measured from the finalization.
+        testing_env!(context.block_timestamp(1_501).build());
+        contract.prune_proposals(vec![id]);
+        assert!(contract.proposals.get(&id).is_none());
+        assert_eq!(
+            contract.get_pruned_proposal(id),
+            ProposalSummary {
+                proposer: accounts(1),
+                kind: "transfer".to_string(),
+                status: ProposalStatus::Rejected,
+                vote_counts: [("council".to_string(), [U128(0), U128(1), U128(0)])]
+                    .into_iter()
+                    .collect(),
+                moved_to: None,
+                failure: None,
+                hash: hash.into(),
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_NOT_PRUNABLE")]
+    fn test_prune_proposal_in_progress() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_retention_period(&mut context);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(1_001).build());
+        contract.prune_proposals(vec![id]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_NOT_PRUNABLE")]
+    fn test_prune_proposal_before_retention_period() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_retention_period(&mut context);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(500).build());
+        contract.act_proposal(
+            id,
+            Action::VoteReject,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        // Retention period has passed since the submission, but not since the finalization.
+        testing_env!(context.block_timestamp(1_001).build());
+        contract.prune_proposals(vec![id]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_NOT_PRUNABLE")]
+    fn test_prune_proposal_max_retention_period() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_retention_period(&mut context);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(500).build());
+        contract.act_proposal(
+            id,
+            Action::VoteReject,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        let mut policy = contract.get_policy();
+        policy.proposal_retention_period = Some(U64(u64::MAX));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        // Retention period past the end of time never passes.
+        testing_env!(context.block_timestamp(u64::MAX).build());
+        contract.prune_proposals(vec![id]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_NOT_PRUNABLE")]
+    fn test_prune_proposal_execution_in_flight() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_retention_period(&mut context);
+        let id = create_proposal(&mut context, &mut contract);
+        // Approved, but the transfer hasn't been confirmed by the callback yet.
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        testing_env!(context.block_timestamp(1_001).build());
+        contract.prune_proposals(vec![id]);
+    }
+
+    #[test]
+    fn test_simulate_action() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        let simulation = contract.simulate_action(id, accounts(1), Action::VoteApprove);
+        assert_eq!(simulation.status, ProposalStatus::InProgress);
+        assert_eq!(simulation.roles.len(), 1);
+        let council = &simulation.roles[0];
+        assert_eq!(council.role, "council");
+        assert_eq!(council.vote_counts[0], U128(1));
+        assert_eq!(council.total_weight, U128(2));
+        assert_eq!(council.threshold, U128(2));
+        assert_eq!(council.remaining_weight, U128(1));
+
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        let simulation = contract.simulate_action(id, accounts(2), Action::VoteApprove);
+        assert_eq!(simulation.status, ProposalStatus::Approved);
+        assert_eq!(simulation.roles[0].remaining_weight, U128(0));
+        // Nothing is written to the state.
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        assert_eq!(proposal.votes.len(), 1);
+        assert_eq!(
+            contract
+                .simulate_action(id, accounts(2), Action::Finalize)
+                .status,
+            ProposalStatus::InProgress
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_simulate_action_permission_denied() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.simulate_action(id, accounts(2), Action::VoteApprove);
+    }
+
+    #[test]
+    fn test_vote_expired_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(
+            context
+                .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+                .build()
+        );
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_VOTED")]
+    fn test_vote_twice() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_WRONG_KIND")]
+    fn test_wrong_kind() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(1), // The only different thing from initial kind
+                amount: U128(NearToken::from_near(100).as_yoctonear()),
+                msg: None,
+            },
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ROLE_NOT_FOUND")]
+    fn test_add_to_missing_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "missing".to_string(),
+            },
+            depends_on: vec![],
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ROLE_WRONG_KIND")]
+    fn test_add_to_everyone_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::RemoveMemberFromRole {
+                member_id: accounts(2),
+                role: "all".to_string(),
+            },
+            depends_on: vec![],
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_BLOB")]
+    fn test_upgrade_remote_missing_blob() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::UpgradeRemote {
+                receiver_id: accounts(2),
+                method_name: "upgrade".to_string(),
+                hash: Base58CryptoHash::from([0u8; 32]),
+            },
+            depends_on: vec![],
+        });
+    }
+
+    #[test]
+    fn test_read_old_policy_proposals() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let policy = default_policy(vec![accounts(2)]);
+        let old_proposal = |kind| {
+            VersionedProposal::V2(ProposalV2 {
+                proposer: accounts(1),
+                description: "old".to_string(),
+                kind,
+                status: ProposalStatus::InProgress,
+                vote_counts: HashMap::default(),
+                votes: HashMap::default(),
+                submission_time: U64(0),
+                last_actions_log: VecDeque::new(),
+            })
+        };
+        // Proposals stored before the policy layout has changed.
+        contract.proposals.insert(
+            &0,
+            &old_proposal(ProposalKindV1::ChangePolicy {
+                policy: VersionedPolicyV1::Current(PolicyV1 {
+                    roles: policy.roles.clone(),
+                    default_vote_policy: policy.default_vote_policy.clone(),
+                    proposal_bond: policy.proposal_bond,
+                    proposal_period: policy.proposal_period,
+                    bounty_bond: policy.bounty_bond,
+                    bounty_forgiveness_period: policy.bounty_forgiveness_period,
+                }),
+            }),
+        );
+        contract.proposals.insert(
+            &1,
+            &old_proposal(ProposalKindV1::ChangePolicyUpdateParameters {
+                parameters: PolicyParametersV1 {
+                    proposal_bond: Some(NearToken::from_near(2)),
+                    proposal_period: None,
+                    bounty_bond: None,
+                    bounty_forgiveness_period: None,
+                },
+            }),
+        );
+        contract.last_proposal_id = 2;
+
+        assert_eq!(
+            contract.get_proposal(0).proposal.kind,
+            ProposalKind::ChangePolicy {
+                policy: VersionedPolicy::Current(policy),
+            }
+        );
+        let ProposalKind::ChangePolicyUpdateParameters { parameters } =
+            contract.get_proposal(1).proposal.kind
+        else {
+            panic!("wrong proposal kind");
+        };
+        assert_eq!(parameters.proposal_bond, Some(NearToken::from_near(2)));
+        assert!(parameters.proposal_bond_overrides.is_empty());
+        assert_eq!(contract.get_proposals(0, 10).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_PERMISSION")]
+    fn test_change_policy_invalid_permission() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.get_policy();
+        policy.roles[1]
+            .permissions
+            .insert("transfer:Approve".to_string());
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangePolicy {
+                policy: VersionedPolicy::Current(policy),
+            },
+            depends_on: vec![],
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DUPLICATE_ROLE")]
+    fn test_change_policy_duplicate_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.get_policy();
+        policy.roles.push(policy.roles[1].clone());
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangePolicy {
+                policy: VersionedPolicy::Current(policy),
+            },
+            depends_on: vec![],
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_POLICY")]
+    fn test_fails_adding_invalid_policy() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        let _id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangePolicy {
+                policy: VersionedPolicy::Default(vec![]),
+            },
+            depends_on: vec![],
+        });
+    }
+
+    /// Creates contract in the state right after the migration, with delegations that weren't iterated yet.
+    fn migrated_delegations_contract(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.staking_id = Some(accounts(4));
+        contract.delegations.insert(&accounts(1), &100);
+        contract.delegations.insert(&accounts(2), &400);
+        contract.total_delegation_amount = 500;
+        contract.quadratic_delegation_recompute = Some(QuadraticDelegationRecompute::default());
+        contract
+    }
+
+    fn add_quadratic_vote_policy_proposal(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+    ) -> u64 {
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(1))
+                .attached_deposit(NearToken::from_near(1))
+                .build()
+        );
+        contract.add_proposal(ProposalInput {
+            description: "quadratic".to_string(),
+            kind: ProposalKind::ChangePolicyUpdateDefaultVotePolicy {
+                vote_policy: VotePolicy {
+                    weight_kind: WeightKind::QuadraticTokenWeight,
+                    ..VotePolicy::default()
+                },
+            },
+            depends_on: vec![],
+        })
+    }
+
+    #[test]
+    fn test_recompute_quadratic_delegation() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = migrated_delegations_contract(&mut context);
+
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .current_account_id(accounts(0))
+                .build()
+        );
+        assert!(!contract.recompute_quadratic_delegation(vec![accounts(1)]));
+
+        // Delegation of already counted account changes during the recompute.
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        contract.delegate(&accounts(1), U128(44));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        assert!(contract.recompute_quadratic_delegation(vec![accounts(2)]));
+        assert!(contract.quadratic_delegation_recompute.is_none());
+        assert_eq!(contract.total_quadratic_delegation_amount, 12 + 20);
+
+        add_quadratic_vote_policy_proposal(&mut context, &mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_QUADRATIC_TOTAL_NOT_RECOMPUTED")]
+    fn test_quadratic_weight_before_recompute() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = migrated_delegations_contract(&mut context);
+        add_quadratic_vote_policy_proposal(&mut context, &mut contract);
+    }
+
+    #[test]
+    fn test_recompute_quadratic_delegation_any_order() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = migrated_delegations_contract(&mut context);
+        // Anyone can recompute, duplicates and accounts without delegation are skipped.
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        assert!(!contract.recompute_quadratic_delegation(vec![
+            accounts(2),
+            accounts(2),
+            accounts(3)
+        ]));
+        assert!(contract.recompute_quadratic_delegation(vec![accounts(2), accounts(1)]));
+        assert_eq!(contract.total_quadratic_delegation_amount, 10 + 20);
+    }
+
+    #[test]
+    fn test_restart_quadratic_delegation_recompute() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = migrated_delegations_contract(&mut context);
+        assert!(!contract.recompute_quadratic_delegation(vec![accounts(1)]));
+
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .current_account_id(accounts(0))
+                .build()
+        );
+        contract.restart_quadratic_delegation_recompute();
+        // Accounts counted before the restart are counted again.
+        assert!(!contract.recompute_quadratic_delegation(vec![accounts(2)]));
+        assert!(contract.recompute_quadratic_delegation(vec![accounts(1)]));
+        assert_eq!(contract.total_quadratic_delegation_amount, 10 + 20);
+    }
+
+    #[test]
+    fn test_recompute_quadratic_delegation_out_of_sync() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = migrated_delegations_contract(&mut context);
+        contract.quadratic_recompute_rounds.insert(&accounts(2), &0);
+        // Account is marked as counted, but its amount isn't in the counted sums.
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        contract.undelegate(&accounts(2), U128(400));
+        assert_eq!(
+            contract
+                .quadratic_delegation_recompute
+                .as_ref()
+                .unwrap()
+                .round,
+            1
+        );
+        assert!(contract.recompute_quadratic_delegation(vec![accounts(1)]));
+        assert_eq!(contract.total_quadratic_delegation_amount, 10);
+    }
+
     #[test]
     fn test_action_log() {
         let mut context = VMContextBuilder::new();
@@ -482,7 +2443,7 @@ mod tests {
             ActionLog {
                 account_id: "alice".parse().unwrap(),
                 proposal_id: 0.into(),
-                action: Action::AddProposal,
+                action: LoggedAction::AddProposal,
                 block_height: 0.into()
             }
         );
@@ -512,7 +2473,7 @@ mod tests {
             ActionLog {
                 account_id: "bob".parse().unwrap(),
                 proposal_id: 0.into(),
-                action: Action::VoteApprove,
+                action: LoggedAction::VoteApprove,
                 block_height: 0.into()
             }
         );
@@ -0,0 +1,139 @@
+use near_sdk::json_types::U128;
+use near_sdk::{Promise, PromiseOrValue, ext_contract};
+
+use crate::types::{GAS_FOR_MT_TRANSFER, GAS_FOR_MT_TRANSFER_CALL, ONE_YOCTO_NEAR};
+use crate::*;
+
+/// Interface of the NEP-245 multi token contract.
+#[allow(dead_code)]
+#[ext_contract(ext_multi_token)]
+pub trait MultiToken {
+    fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<String>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    );
+    fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<String>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+}
+
+impl Contract {
+    /// Transfers given amounts of the multi tokens of this DAO to the receiver in a single batch.
+    /// If `msg` is not None, calls `mt_batch_transfer_call` with given `msg`.
+    pub(crate) fn internal_mt_transfer(
+        &self,
+        mt_contract_id: &AccountId,
+        receiver_id: &AccountId,
+        tokens: &[(String, U128)],
+        memo: String,
+        msg: Option<String>,
+    ) -> Promise {
+        let (token_ids, amounts) = tokens.iter().cloned().unzip();
+        let mt = ext_multi_token::ext(mt_contract_id.clone()).with_attached_deposit(ONE_YOCTO_NEAR);
+        if let Some(msg) = msg {
+            mt.with_static_gas(GAS_FOR_MT_TRANSFER_CALL)
+                .mt_batch_transfer_call(
+                    receiver_id.clone(),
+                    token_ids,
+                    amounts,
+                    None,
+                    Some(memo),
+                    msg,
+                )
+        } else {
+            mt.with_static_gas(GAS_FOR_MT_TRANSFER).mt_batch_transfer(
+                receiver_id.clone(),
+                token_ids,
+                amounts,
+                None,
+                Some(memo),
+            )
+        }
+    }
+}
+
+#[near]
+impl Contract {
+    /// NEP-245 receiver: accepts all the received multi tokens into the treasury.
+    /// Returns amounts to refund, which are always zero.
+    #[allow(unused_variables)]
+    pub fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<String>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        PromiseOrValue::Value(vec![U128(0); amounts.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_api::types::NearToken;
+    use near_sdk::test_utils::{VMContextBuilder, accounts};
+    use near_sdk::testing_env;
+
+    use crate::Config;
+    use crate::proposals::{ProposalInput, ProposalKind};
+
+    use super::*;
+    use crate::tests::add_approved_proposal;
+
+    #[test]
+    fn test_mt_transfer() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_approved_proposal(
+            &mut context,
+            &mut contract,
+            ProposalKind::MtTransfer {
+                mt_contract_id: accounts(3),
+                receiver_id: accounts(2),
+                tokens: vec![("a".to_string(), U128(1)), ("b".to_string(), U128(2))],
+                msg: None,
+            },
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_MT_TRANSFER")]
+    fn test_mt_transfer_zero_amount() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::MtTransfer {
+                mt_contract_id: accounts(3),
+                receiver_id: accounts(2),
+                tokens: vec![("a".to_string(), U128(0))],
+                msg: None,
+            },
+            depends_on: vec![],
+        });
+    }
+}
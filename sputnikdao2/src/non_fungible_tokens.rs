@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use near_contract_standards::non_fungible_token::TokenId;
+use near_contract_standards::non_fungible_token::core::NonFungibleTokenReceiver;
+use near_sdk::{Promise, PromiseOrValue, ext_contract};
+
+use crate::types::{GAS_FOR_NFT_TRANSFER, GAS_FOR_NFT_TRANSFER_CALL, ONE_YOCTO_NEAR};
+use crate::*;
+
+/// Interface of the NEP-171 non-fungible token contract.
+#[allow(dead_code)]
+#[ext_contract(ext_non_fungible_token)]
+pub trait NonFungibleToken {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    );
+    fn nft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool>;
+}
+
+impl Contract {
+    /// Transfers given NFT of this DAO to the receiver.
+    /// If `msg` is not None, calls `nft_transfer_call` with given `msg`.
+    pub(crate) fn internal_nft_transfer(
+        &self,
+        nft_contract_id: &AccountId,
+        token_id: &TokenId,
+        receiver_id: &AccountId,
+        memo: String,
+        msg: Option<String>,
+    ) -> Promise {
+        let nft = ext_non_fungible_token::ext(nft_contract_id.clone())
+            .with_attached_deposit(ONE_YOCTO_NEAR);
+        if let Some(msg) = msg {
+            nft.with_static_gas(GAS_FOR_NFT_TRANSFER_CALL)
+                .nft_transfer_call(receiver_id.clone(), token_id.clone(), None, Some(memo), msg)
+        } else {
+            nft.with_static_gas(GAS_FOR_NFT_TRANSFER).nft_transfer(
+                receiver_id.clone(),
+                token_id.clone(),
+                None,
+                Some(memo),
+            )
+        }
+    }
+
+    /// Removes given NFT from the holdings of this DAO.
+    /// Storage it has used is credited back to the storage deposit of the NFT contract.
+    pub(crate) fn internal_remove_nft(&mut self, nft_contract_id: &AccountId, token_id: &TokenId) {
+        let Some(mut tokens) = self.nft_holdings.get(nft_contract_id) else {
+            return;
+        };
+        if !tokens.remove(token_id) {
+            return;
+        }
+        let initial_storage = env::storage_usage();
+        self.internal_set_nft_holdings(nft_contract_id, &tokens);
+        let storage_cost = env::storage_byte_cost()
+            .saturating_mul(initial_storage.saturating_sub(env::storage_usage()).into());
+        let balance = self.get_nft_storage_balance(nft_contract_id.clone());
+        self.nft_storage_balances
+            .insert(nft_contract_id, &balance.saturating_add(storage_cost));
+        self.locked_amount = self.locked_amount.saturating_add(storage_cost);
+    }
+
+    /// Stores given NFTs as the holdings of this DAO for given NFT contract.
+    fn internal_set_nft_holdings(
+        &mut self,
+        nft_contract_id: &AccountId,
+        tokens: &HashSet<TokenId>,
+    ) {
+        if tokens.is_empty() {
+            self.nft_holdings.remove(nft_contract_id);
+        } else {
+            self.nft_holdings.insert(nft_contract_id, tokens);
+        }
+    }
+}
+
+#[near]
+impl NonFungibleTokenReceiver for Contract {
+    /// Records the received NFT in the holdings of this DAO and keeps it.
+    /// Storage of the record is paid from the storage deposit of the NFT contract,
+    /// the NFT is returned if the deposit is not enough.
+    #[allow(unused_variables)]
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let nft_contract_id = env::predecessor_account_id();
+        let mut tokens = self.nft_holdings.get(&nft_contract_id).unwrap_or_default();
+        if !tokens.insert(token_id.clone()) {
+            return PromiseOrValue::Value(false);
+        }
+        let initial_storage = env::storage_usage();
+        self.nft_holdings.insert(&nft_contract_id, &tokens);
+        let storage_cost = env::storage_byte_cost()
+            .saturating_mul(env::storage_usage().saturating_sub(initial_storage).into());
+        let balance = self.get_nft_storage_balance(nft_contract_id.clone());
+        if balance < storage_cost {
+            tokens.remove(&token_id);
+            self.internal_set_nft_holdings(&nft_contract_id, &tokens);
+            return PromiseOrValue::Value(true);
+        }
+        self.nft_storage_balances
+            .insert(&nft_contract_id, &balance.saturating_sub(storage_cost));
+        self.locked_amount = self.locked_amount.saturating_sub(storage_cost);
+        PromiseOrValue::Value(false)
+    }
+}
+
+#[near]
+impl Contract {
+    /// Returns ids of the NFTs of given contract, received by this DAO with `nft_transfer_call`.
+    pub fn get_nft_holdings(&self, nft_contract_id: AccountId) -> HashSet<TokenId> {
+        self.nft_holdings.get(&nft_contract_id).unwrap_or_default()
+    }
+
+    /// Returns the deposit left for the storage of the NFTs of given contract.
+    pub fn get_nft_storage_balance(&self, nft_contract_id: AccountId) -> NearToken {
+        self.nft_storage_balances
+            .get(&nft_contract_id)
+            .unwrap_or(NearToken::from_yoctonear(0))
+    }
+
+    /// Adds attached deposit to the storage deposit of given NFT contract, used to record
+    /// the NFTs received from it. Storage of the deposit record itself is paid from it.
+    /// The deposit is locked and can't be spent otherwise. Returns the new storage deposit.
+    #[payable]
+    pub fn nft_storage_deposit(&mut self, nft_contract_id: AccountId) -> NearToken {
+        let initial_storage = env::storage_usage();
+        let balance = self
+            .get_nft_storage_balance(nft_contract_id.clone())
+            .saturating_add(env::attached_deposit());
+        self.nft_storage_balances.insert(&nft_contract_id, &balance);
+        let storage_cost = env::storage_byte_cost()
+            .saturating_mul(env::storage_usage().saturating_sub(initial_storage).into());
+        let deposit = env::attached_deposit()
+            .checked_sub(storage_cost)
+            .expect("ERR_NOT_ENOUGH_DEPOSIT");
+        let balance = balance.saturating_sub(storage_cost);
+        self.nft_storage_balances.insert(&nft_contract_id, &balance);
+        self.locked_amount = self.locked_amount.saturating_add(deposit);
+        balance
+    }
+
+    /// Releases the storage deposit of given NFT contract that isn't used by the recorded NFTs
+    /// to the treasury, e.g. with a `FunctionCall` proposal. NFTs received from the contract
+    /// afterwards are returned until a new deposit is made. Returns the released amount.
+    #[private]
+    pub fn nft_storage_release(&mut self, nft_contract_id: AccountId) -> NearToken {
+        let balance = self
+            .nft_storage_balances
+            .remove(&nft_contract_id)
+            .unwrap_or(NearToken::from_yoctonear(0));
+        self.locked_amount = self.locked_amount.saturating_sub(balance);
+        balance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_api::types::NearToken;
+    use near_sdk::test_utils::{VMContextBuilder, accounts};
+    use near_sdk::{PromiseResult, testing_env};
+
+    use crate::Config;
+    use crate::proposals::ProposalKind;
+
+    use super::*;
+    use crate::tests::add_approved_proposal;
+
+    /// Creates contract holding NFTs "1" and "2" of `accounts(3)`.
+    fn contract_with_nfts(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        contract.nft_storage_deposit(accounts(3));
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(3))
+                .attached_deposit(NearToken::from_near(0))
+                .build()
+        );
+        for token_id in ["1", "2"] {
+            assert!(matches!(
+                contract.nft_on_transfer(
+                    accounts(2),
+                    accounts(2),
+                    token_id.to_string(),
+                    String::new(),
+                ),
+                PromiseOrValue::Value(false)
+            ));
+        }
+        contract
+    }
+
+    fn transfer_nft(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        msg: Option<String>,
+        result: PromiseResult,
+    ) {
+        context.predecessor_account_id(accounts(1));
+        let id = add_approved_proposal(
+            context,
+            contract,
+            ProposalKind::NftTransfer {
+                nft_contract_id: accounts(3),
+                token_id: "1".to_string(),
+                receiver_id: accounts(2),
+                msg,
+            },
+        );
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+        let _ = contract.on_proposal_callback(id);
+    }
+
+    #[test]
+    fn test_nft_holdings() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_nfts(&mut context);
+        assert_eq!(contract.get_nft_holdings(accounts(3)).len(), 2);
+        let storage_balance = contract.get_nft_storage_balance(accounts(3));
+        assert!(storage_balance < NearToken::from_near(1));
+
+        transfer_nft(
+            &mut context,
+            &mut contract,
+            None,
+            PromiseResult::Successful(vec![]),
+        );
+        assert_eq!(
+            contract.get_nft_holdings(accounts(3)),
+            ["2".to_string()].into_iter().collect()
+        );
+        // Freed storage is credited back.
+        assert!(contract.get_nft_storage_balance(accounts(3)) > storage_balance);
+    }
+
+    #[test]
+    fn test_nft_transfer_call_returned() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_nfts(&mut context);
+        transfer_nft(
+            &mut context,
+            &mut contract,
+            Some("deposit".to_string()),
+            PromiseResult::Successful(b"false".to_vec()),
+        );
+        assert_eq!(contract.get_nft_holdings(accounts(3)).len(), 2);
+    }
+
+    #[test]
+    fn test_nft_storage_release() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_nfts(&mut context);
+        let storage_balance = contract.get_nft_storage_balance(accounts(3));
+        let locked_amount = contract.locked_amount;
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        assert_eq!(contract.nft_storage_release(accounts(3)), storage_balance);
+        assert_eq!(
+            contract.get_nft_storage_balance(accounts(3)),
+            NearToken::from_yoctonear(0)
+        );
+        assert_eq!(
+            contract.locked_amount,
+            locked_amount.saturating_sub(storage_balance)
+        );
+        // Recorded NFTs are kept, new ones are returned.
+        assert_eq!(contract.get_nft_holdings(accounts(3)).len(), 2);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        assert!(matches!(
+            contract.nft_on_transfer(accounts(2), accounts(2), "3".to_string(), String::new()),
+            PromiseOrValue::Value(true)
+        ));
+    }
+
+    #[test]
+    fn test_nft_without_storage_deposit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        // NFT is returned, as nobody has paid for its storage.
+        assert!(matches!(
+            contract.nft_on_transfer(accounts(2), accounts(2), "1".to_string(), String::new()),
+            PromiseOrValue::Value(true)
+        ));
+        assert!(contract.get_nft_holdings(accounts(3)).is_empty());
+    }
+}
@@ -110,11 +110,25 @@ impl WeightOrRatio {
 #[derive(Clone, PartialEq)]
 #[near(serializers=[json,borsh])]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[allow(clippy::enum_variant_names)]
 pub enum WeightKind {
     /// Using token amounts and total delegated at the moment.
     TokenWeight,
     /// Weight of the group role. Roles that don't have scoped group are not supported.
     RoleWeight,
+    /// Using integer square root of token amounts and the sum of square roots of all delegations,
+    /// so that large holders have less influence.
+    QuadraticTokenWeight,
+}
+
+impl WeightKind {
+    /// Returns if votes are weighted by the delegated tokens.
+    pub fn is_token_weight(&self) -> bool {
+        matches!(
+            self,
+            WeightKind::TokenWeight | WeightKind::QuadraticTokenWeight
+        )
+    }
 }
 
 /// Defines configuration of the vote.
@@ -128,6 +142,7 @@ pub struct VotePolicy {
     /// Minimum number required for vote to finalize.
     /// If weight kind is TokenWeight - this is minimum number of tokens required.
     ///     This allows to avoid situation where the number of staked tokens from total supply is too small.
+    /// If QuadraticTokenWeight - this is minimum sum of square roots of tokens.
     /// If RoleWeight - this is minimum number of votes.
     ///     This allows to avoid situation where the role is got too small but policy kept at 1/2, for example.
     pub quorum: U128,
@@ -163,6 +178,126 @@ pub struct Policy {
     pub bounty_bond: NearToken,
     /// Period in which giving up on bounty is not punished.
     pub bounty_forgiveness_period: U64,
+    /// For each proposal kind, defines delay between approval and execution.
+    /// Proposal kinds without delay are executed right after approval.
+    #[serde(default)]
+    pub execution_delay: HashMap<String, U64>,
+    /// For each proposal kind, overrides the proposal bond.
+    #[serde(default)]
+    pub proposal_bond_overrides: HashMap<String, NearToken>,
+    /// For each proposal kind, overrides the expiration period.
+    #[serde(default)]
+    pub proposal_period_overrides: HashMap<String, U64>,
+    /// Bond in fungible token, that can be paid instead of the proposal bond in NEAR
+    /// by transferring the token to the DAO with the proposal in the message.
+    #[serde(default)]
+    pub proposal_bond_token: Option<TokenBond>,
+    /// DAO that proposals can be moved to with `MoveToHub` action.
+    #[serde(default)]
+    pub hub_id: Option<AccountId>,
+    /// Period after submission when finalized proposals can be pruned.
+    /// Proposals can't be pruned if not set.
+    #[serde(default)]
+    pub proposal_retention_period: Option<U64>,
+    /// How many times a failed proposal can be finalized again before it expires.
+    /// Unlimited if not set.
+    #[serde(default)]
+    pub failed_proposal_max_retries: Option<u32>,
+    /// Maximum amount of $NEAR paid from the treasury to register the receiver of
+    /// a fungible token payout with the token's storage, if it's not registered yet.
+    /// Receivers are not registered if not set.
+    #[serde(default)]
+    pub ft_storage_deposit: Option<NearToken>,
+}
+
+/// Problem found in the policy by `Policy::validate`.
+#[derive(Clone, PartialEq)]
+#[near(serializers=[json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub enum PolicyFinding {
+    /// More than one role has this name.
+    DuplicateRole { role: String },
+    /// Permission is not in the `<proposal_kind>:<action>` format.
+    InvalidPermission { role: String, permission: String },
+    /// Permission refers to an unknown proposal kind.
+    UnknownProposalKind { role: String, label: String },
+    /// Permission refers to an unknown action.
+    UnknownAction { role: String, action: String },
+    /// Vote policy is set for an unknown proposal kind.
+    UnknownVotePolicyLabel { role: String, label: String },
+    /// Group role has no members.
+    EmptyGroup { role: String },
+    /// Vote policy is token weighted, but the DAO has no staking contract.
+    /// Role and label are not set for the default vote policy.
+    TokenWeightWithoutStaking {
+        role: Option<String>,
+        label: Option<String>,
+    },
+    /// Quorum of the vote policy is larger than the group, so it can never be met.
+    /// Label is not set for the default vote policy.
+    UnreachableThreshold { role: String, label: Option<String> },
+}
+
+/// Bond amount in the given fungible token.
+#[derive(Clone, PartialEq)]
+#[near(serializers=[borsh, json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(deny_unknown_fields)]
+pub struct TokenBond {
+    pub token_id: AccountId,
+    pub amount: U128,
+}
+
+/// Policy before execution delays were introduced. Used to migrate the state.
+#[derive(Clone, PartialEq)]
+#[near(serializers=[borsh])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct PolicyV1 {
+    pub roles: Vec<RolePermission>,
+    pub default_vote_policy: VotePolicy,
+    pub proposal_bond: NearToken,
+    pub proposal_period: U64,
+    pub bounty_bond: NearToken,
+    pub bounty_forgiveness_period: U64,
+}
+
+impl From<PolicyV1> for Policy {
+    fn from(v1: PolicyV1) -> Self {
+        Policy {
+            roles: v1.roles,
+            default_vote_policy: v1.default_vote_policy,
+            proposal_bond: v1.proposal_bond,
+            proposal_period: v1.proposal_period,
+            bounty_bond: v1.bounty_bond,
+            bounty_forgiveness_period: v1.bounty_forgiveness_period,
+            execution_delay: HashMap::default(),
+            proposal_bond_overrides: HashMap::default(),
+            proposal_period_overrides: HashMap::default(),
+            proposal_bond_token: None,
+            hub_id: None,
+            proposal_retention_period: None,
+            failed_proposal_max_retries: None,
+            ft_storage_deposit: None,
+        }
+    }
+}
+
+/// Versioned policy before execution delays were introduced. Used to migrate the state.
+#[derive(Clone, PartialEq)]
+#[near(serializers=[borsh])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub enum VersionedPolicyV1 {
+    Default(Vec<AccountId>),
+    Current(PolicyV1),
+}
+
+impl From<VersionedPolicyV1> for VersionedPolicy {
+    fn from(v1: VersionedPolicyV1) -> Self {
+        match v1 {
+            VersionedPolicyV1::Default(accounts) => VersionedPolicy::Default(accounts),
+            VersionedPolicyV1::Current(policy) => VersionedPolicy::Current(policy.into()),
+        }
+    }
 }
 
 /// Versioned policy.
@@ -170,6 +305,7 @@ pub struct Policy {
 #[near(serializers = [borsh, json])]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
 #[serde(untagged, deny_unknown_fields)]
+#[allow(clippy::large_enum_variant)]
 pub enum VersionedPolicy {
     /// Default policy with given accounts as council.
     Default(Vec<AccountId>),
@@ -212,6 +348,14 @@ pub fn default_policy(council: Vec<AccountId>) -> Policy {
         proposal_period: U64::from(1_000_000_000 * 60 * 60 * 24 * 7),
         bounty_bond: NearToken::from_near(1),
         bounty_forgiveness_period: U64::from(1_000_000_000 * 60 * 60 * 24),
+        execution_delay: HashMap::default(),
+        proposal_bond_overrides: HashMap::default(),
+        proposal_period_overrides: HashMap::default(),
+        proposal_bond_token: None,
+        hub_id: None,
+        proposal_retention_period: None,
+        failed_proposal_max_retries: None,
+        ft_storage_deposit: None,
     }
 }
 
@@ -242,6 +386,31 @@ impl VersionedPolicy {
     }
 }
 
+/// Returns if given set of permissions allows given action on given kind of proposal.
+/// Batch proposals also require permission for each of the inner proposal kinds.
+fn is_action_allowed(
+    permissions: &HashSet<String>,
+    proposal_kind: &ProposalKind,
+    action: &Action,
+) -> bool {
+    let allowed = permissions.contains(&format!(
+        "{}:{}",
+        proposal_kind.to_policy_label(),
+        action.to_policy_label()
+    )) || permissions.contains(&format!("{}:*", proposal_kind.to_policy_label()))
+        || permissions.contains(&format!("*:{}", action.to_policy_label()))
+        || permissions.contains("*:*");
+    match proposal_kind {
+        ProposalKind::Batch { kinds } => {
+            allowed
+                && kinds
+                    .iter()
+                    .all(|kind| is_action_allowed(permissions, kind, action))
+        }
+        _ => allowed,
+    }
+}
+
 impl Policy {
     pub fn add_or_update_role(&mut self, role: &RolePermission) {
         for i in 0..self.roles.len() {
@@ -286,9 +455,122 @@ impl Policy {
         if parameters.bounty_forgiveness_period.is_some() {
             self.bounty_forgiveness_period = parameters.bounty_forgiveness_period.unwrap();
         }
+        for (label, bond) in &parameters.proposal_bond_overrides {
+            match bond {
+                Some(bond) => self.proposal_bond_overrides.insert(label.clone(), *bond),
+                None => self.proposal_bond_overrides.remove(label),
+            };
+        }
+        for (label, period) in &parameters.proposal_period_overrides {
+            match period {
+                Some(period) => self
+                    .proposal_period_overrides
+                    .insert(label.clone(), *period),
+                None => self.proposal_period_overrides.remove(label),
+            };
+        }
         env::log_str("Successfully updated the policy parameters.");
     }
 
+    /// Returns problems found in the policy.
+    /// Token weighted vote policies are reported if the DAO has no staking contract.
+    pub fn validate(&self, has_staking_contract: bool) -> Vec<PolicyFinding> {
+        let mut findings = vec![];
+        let mut names = HashSet::new();
+        for role in self.roles.iter() {
+            if !names.insert(&role.name) {
+                findings.push(PolicyFinding::DuplicateRole {
+                    role: role.name.clone(),
+                });
+            }
+            if role.kind.get_role_size() == Some(0) {
+                findings.push(PolicyFinding::EmptyGroup {
+                    role: role.name.clone(),
+                });
+            }
+            for permission in role.permissions.iter() {
+                let Some((label, action)) = permission.split_once(':') else {
+                    findings.push(PolicyFinding::InvalidPermission {
+                        role: role.name.clone(),
+                        permission: permission.clone(),
+                    });
+                    continue;
+                };
+                if label != "*" && !ProposalKind::LABELS.contains(&label) {
+                    findings.push(PolicyFinding::UnknownProposalKind {
+                        role: role.name.clone(),
+                        label: label.to_string(),
+                    });
+                }
+                if action != "*" && !Action::LABELS.contains(&action) {
+                    findings.push(PolicyFinding::UnknownAction {
+                        role: role.name.clone(),
+                        action: action.to_string(),
+                    });
+                }
+            }
+            let vote_policies = role
+                .vote_policy
+                .iter()
+                .map(|(label, vote_policy)| (Some(label), vote_policy))
+                .chain(std::iter::once((None, &self.default_vote_policy)));
+            for (label, vote_policy) in vote_policies {
+                if label.is_some_and(|label| !ProposalKind::LABELS.contains(&label.as_str())) {
+                    findings.push(PolicyFinding::UnknownVotePolicyLabel {
+                        role: role.name.clone(),
+                        label: label.unwrap().clone(),
+                    });
+                }
+                // Default vote policy is reported once below, not for every role.
+                if label.is_some()
+                    && vote_policy.weight_kind.is_token_weight()
+                    && !has_staking_contract
+                {
+                    findings.push(PolicyFinding::TokenWeightWithoutStaking {
+                        role: Some(role.name.clone()),
+                        label: label.cloned(),
+                    });
+                }
+                // Role weight of a group is the number of its members, quorum above it can't be met.
+                if let (Some(size), WeightKind::RoleWeight) =
+                    (role.kind.get_role_size(), &vote_policy.weight_kind)
+                {
+                    if vote_policy.quorum.0 > size as u128 {
+                        findings.push(PolicyFinding::UnreachableThreshold {
+                            role: role.name.clone(),
+                            label: label.cloned(),
+                        });
+                    }
+                }
+            }
+        }
+        if self.default_vote_policy.weight_kind.is_token_weight() && !has_staking_contract {
+            findings.push(PolicyFinding::TokenWeightWithoutStaking {
+                role: None,
+                label: None,
+            });
+        }
+        findings
+    }
+
+    /// Asserts that roles have unique names, permissions are well-formed
+    /// and vote policies are set for known proposal kinds.
+    /// Other findings don't make the policy invalid, e.g. staking contract can be set later.
+    pub fn assert_valid(&self) {
+        for finding in self.validate(true) {
+            match finding {
+                PolicyFinding::DuplicateRole { .. } => env::panic_str("ERR_DUPLICATE_ROLE"),
+                PolicyFinding::InvalidPermission { .. }
+                | PolicyFinding::UnknownProposalKind { .. }
+                | PolicyFinding::UnknownAction { .. } => env::panic_str("ERR_INVALID_PERMISSION"),
+                PolicyFinding::UnknownVotePolicyLabel { .. } => {
+                    env::panic_str("ERR_UNKNOWN_VOTE_POLICY_LABEL")
+                }
+                _ => {}
+            }
+        }
+    }
+
     pub fn add_member_to_role(&mut self, role: &String, member_id: &AccountId) {
         for i in 0..self.roles.len() {
             if &self.roles[i].name == role {
@@ -343,14 +625,7 @@ impl Policy {
         let allowed_roles = roles
             .into_iter()
             .filter_map(|(role, permissions)| {
-                let allowed_role = permissions.contains(&format!(
-                    "{}:{}",
-                    proposal_kind.to_policy_label(),
-                    action.to_policy_label()
-                )) || permissions
-                    .contains(&format!("{}:*", proposal_kind.to_policy_label()))
-                    || permissions.contains(&format!("*:{}", action.to_policy_label()))
-                    || permissions.contains("*:*");
+                let allowed_role = is_action_allowed(permissions, proposal_kind, action);
                 allowed = allowed || allowed_role;
                 if allowed_role { Some(role) } else { None }
             })
@@ -360,28 +635,65 @@ impl Policy {
 
     /// Returns if given proposal kind is token weighted.
     pub fn is_token_weighted(&self, role: &String, proposal_kind_label: &String) -> bool {
+        self.get_weight_kind(role, proposal_kind_label)
+            .is_token_weight()
+    }
+
+    /// Returns how votes of given role are weighted for given proposal kind.
+    pub fn get_weight_kind(&self, role: &String, proposal_kind_label: &String) -> &WeightKind {
         let role_info = self.internal_get_role(role).expect("ERR_ROLE_NOT_FOUND");
-        matches!(
-            role_info
-                .vote_policy
-                .get(proposal_kind_label)
-                .unwrap_or(&self.default_vote_policy)
-                .weight_kind,
-            WeightKind::TokenWeight
-        )
+        &role_info
+            .vote_policy
+            .get(proposal_kind_label)
+            .unwrap_or(&self.default_vote_policy)
+            .weight_kind
     }
 
-    fn internal_get_role(&self, name: &String) -> Option<&RolePermission> {
+    /// Returns delay between approval and execution for given proposal kind.
+    pub fn get_execution_delay(&self, proposal_kind_label: &str) -> u64 {
+        self.execution_delay
+            .get(proposal_kind_label)
+            .map(|delay| delay.0)
+            .unwrap_or_default()
+    }
+
+    /// Returns the bond required to add a proposal of the given kind.
+    pub fn get_proposal_bond(&self, proposal_kind_label: &str) -> NearToken {
+        self.proposal_bond_overrides
+            .get(proposal_kind_label)
+            .copied()
+            .unwrap_or(self.proposal_bond)
+    }
+
+    /// Returns the expiration period of a proposal of the given kind.
+    pub fn get_proposal_period(&self, proposal_kind_label: &str) -> u64 {
+        self.proposal_period_overrides
+            .get(proposal_kind_label)
+            .unwrap_or(&self.proposal_period)
+            .0
+    }
+
+    pub(crate) fn internal_get_role(&self, name: &String) -> Option<&RolePermission> {
         self.roles.iter().find(|role| &role.name == name)
     }
 
+    /// Returns false if the proposal has failed more times than the policy allows to retry it.
+    pub fn can_retry(&self, proposal: &Proposal) -> bool {
+        proposal.status != ProposalStatus::Failed
+            || self.failed_proposal_max_retries.is_none_or(|max_retries| {
+                proposal.failure.as_ref().map_or(0, |failure| failure.count) <= max_retries
+            })
+    }
+
     /// Get proposal status for given proposal.
     /// Usually is called after changing it's state.
+    /// `quadratic_total_supply` is the sum of square roots of all delegations.
     pub fn proposal_status(
         &self,
         proposal: &Proposal,
         roles: Vec<String>,
         total_supply: Balance,
+        quadratic_total_supply: Balance,
     ) -> ProposalStatus {
         assert!(
             matches!(
@@ -390,32 +702,24 @@ impl Policy {
             ),
             "ERR_PROPOSAL_NOT_IN_PROGRESS"
         );
-        if proposal.submission_time.0 + self.proposal_period.0 < env::block_timestamp() {
+        if proposal.submission_time.0 + self.get_proposal_period(proposal.kind.to_policy_label())
+            < env::block_timestamp()
+        {
             // Proposal expired.
             return ProposalStatus::Expired;
         };
         for role in roles {
             let role_info = self.internal_get_role(&role).expect("ERR_MISSING_ROLE");
-            let vote_policy = role_info
-                .vote_policy
-                .get(proposal.kind.to_policy_label())
-                .unwrap_or(&self.default_vote_policy);
-            let total_weight = match &role_info.kind {
-                // Skip role that covers everyone as it doesn't provide a total size.
-                RoleKind::Everyone => continue,
-                RoleKind::Group(group) => {
-                    if vote_policy.weight_kind == WeightKind::RoleWeight {
-                        group.len() as Balance
-                    } else {
-                        total_supply
-                    }
-                }
-                RoleKind::Member(_) => total_supply,
+            // Skip role that covers everyone as it doesn't provide a total size.
+            let Some((_, threshold)) = self.internal_get_role_threshold(
+                role_info,
+                proposal,
+                total_supply,
+                quadratic_total_supply,
+            ) else {
+                continue;
             };
-            let threshold = U128::from(std::cmp::max(
-                vote_policy.quorum.0,
-                vote_policy.threshold.to_weight(total_weight),
-            ));
+            let threshold = U128::from(threshold);
             let proposal_data = proposal;
             // Check if there is anything voted above the threshold specified by policy for given role.
             let defaults = [U128::from(0); 3];
@@ -430,8 +734,82 @@ impl Policy {
                 // continue to next role.
             }
         }
+        if proposal.status == ProposalStatus::InProgress && self.is_approval_impossible(proposal) {
+            // Outcome can't change anymore, no need to wait until the proposal expires.
+            return ProposalStatus::Rejected;
+        }
         proposal.status.clone()
     }
+
+    /// Returns total weight and threshold of the given role for given proposal.
+    /// Returns None for a role that covers everyone as it doesn't provide a total size.
+    pub(crate) fn internal_get_role_threshold(
+        &self,
+        role_info: &RolePermission,
+        proposal: &Proposal,
+        total_supply: Balance,
+        quadratic_total_supply: Balance,
+    ) -> Option<(Balance, Balance)> {
+        let vote_policy = role_info
+            .vote_policy
+            .get(proposal.kind.to_policy_label())
+            .unwrap_or(&self.default_vote_policy);
+        let total_supply = if vote_policy.weight_kind == WeightKind::QuadraticTokenWeight {
+            quadratic_total_supply
+        } else {
+            total_supply
+        };
+        let total_weight = match &role_info.kind {
+            RoleKind::Everyone => return None,
+            RoleKind::Group(group) => {
+                if vote_policy.weight_kind == WeightKind::RoleWeight {
+                    group.len() as Balance
+                } else {
+                    total_supply
+                }
+            }
+            RoleKind::Member(_) => total_supply,
+        };
+        let threshold = std::cmp::max(
+            vote_policy.quorum.0,
+            vote_policy.threshold.to_weight(total_weight),
+        );
+        Some((total_weight, threshold))
+    }
+
+    /// Returns true if no role can reach its approval threshold anymore: even if all the weight
+    /// that hasn't voted to reject or remove yet votes to approve, it stays below the threshold.
+    /// Roles are checked regardless of their permissions, as votes are counted in every role of the voter.
+    /// Only groups voting by role weight have a known total weight: delegated token supply can still grow,
+    /// so roles voting by token weight can always reach their threshold.
+    fn is_approval_impossible(&self, proposal: &Proposal) -> bool {
+        let defaults = [U128::from(0); 3];
+        self.roles.iter().all(|role_info| {
+            let vote_policy = role_info
+                .vote_policy
+                .get(proposal.kind.to_policy_label())
+                .unwrap_or(&self.default_vote_policy);
+            let total_weight = match &role_info.kind {
+                RoleKind::Everyone => return true,
+                RoleKind::Group(group) if vote_policy.weight_kind == WeightKind::RoleWeight => {
+                    group.len() as Balance
+                }
+                _ => return false,
+            };
+            let threshold = std::cmp::max(
+                vote_policy.quorum.0,
+                vote_policy.threshold.to_weight(total_weight),
+            );
+            let vote_counts = proposal
+                .vote_counts
+                .get(&role_info.name)
+                .unwrap_or(&defaults);
+            let max_approve_weight = total_weight
+                .saturating_sub(vote_counts[Vote::Reject as usize].0)
+                .saturating_sub(vote_counts[Vote::Remove as usize].0);
+            max_approve_weight < threshold
+        })
+    }
 }
 
 #[cfg(test)]
@@ -439,6 +817,7 @@ mod tests {
     use near_sdk::test_utils::accounts;
 
     use super::*;
+    use crate::proposals::{ProposalInput, VersionedProposal};
 
     #[test]
     fn test_vote_policy() {
@@ -452,6 +831,105 @@ mod tests {
         assert_eq!(r2.to_weight(5), 5);
     }
 
+    #[test]
+    fn test_quadratic_token_weight() {
+        let mut policy = default_policy(vec![accounts(0)]);
+        policy.default_vote_policy = VotePolicy {
+            weight_kind: WeightKind::QuadraticTokenWeight,
+            quorum: U128(0),
+            threshold: WeightOrRatio::Ratio(1, std::num::NonZeroU64::new(2).unwrap()),
+        };
+        let mut proposal: Proposal = VersionedProposal::from(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            depends_on: vec![],
+        })
+        .into();
+        let roles = vec!["council".to_string()];
+
+        // Three delegations of 100 and one of 99: square roots sum up to 39, threshold is 20.
+        proposal.update_votes(&accounts(1), &roles, Vote::Approve, &policy, 100);
+        proposal.update_votes(&accounts(2), &roles, Vote::Approve, &policy, 99);
+        assert_eq!(proposal.vote_counts["council"][0], U128(19));
+        assert_eq!(
+            policy.proposal_status(&proposal, roles.clone(), 399, 39),
+            ProposalStatus::InProgress
+        );
+        proposal.update_votes(&accounts(3), &roles, Vote::Approve, &policy, 100);
+        assert_eq!(
+            policy.proposal_status(&proposal, roles, 399, 39),
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_token_weight_approval_not_impossible() {
+        let mut policy = default_policy(vec![accounts(0)]);
+        policy.default_vote_policy = VotePolicy {
+            weight_kind: WeightKind::TokenWeight,
+            quorum: U128(1000),
+            threshold: WeightOrRatio::Ratio(1, std::num::NonZeroU64::new(2).unwrap()),
+        };
+        let mut proposal: Proposal = VersionedProposal::from(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            depends_on: vec![],
+        })
+        .into();
+        let roles = vec!["council".to_string()];
+
+        // Quorum is above the delegated supply and half of it has rejected,
+        // but more tokens can still be delegated to approve.
+        proposal
+            .vote_counts
+            .insert("council".to_string(), [U128(0), U128(50), U128(0)]);
+        assert_eq!(
+            policy.proposal_status(&proposal, roles, 100, 10),
+            ProposalStatus::InProgress
+        );
+    }
+
+    #[test]
+    fn test_batch_permissions() {
+        let mut policy = default_policy(vec![accounts(0)]);
+        policy.roles[0].permissions = vec![
+            "batch:AddProposal".to_string(),
+            "vote:AddProposal".to_string(),
+        ]
+        .into_iter()
+        .collect();
+        let user = || UserInfo {
+            account_id: accounts(1),
+            amount: 0,
+        };
+        let batch = |kinds| ProposalKind::Batch { kinds };
+
+        assert!(
+            policy
+                .can_execute_action(
+                    user(),
+                    &batch(vec![ProposalKind::Vote]),
+                    &Action::AddProposal
+                )
+                .1
+        );
+        // Not allowed to add one of the inner proposal kinds.
+        assert!(
+            !policy
+                .can_execute_action(
+                    user(),
+                    &batch(vec![
+                        ProposalKind::Vote,
+                        ProposalKind::SetStakingContract {
+                            staking_id: accounts(2)
+                        }
+                    ]),
+                    &Action::AddProposal
+                )
+                .1
+        );
+    }
+
     #[test]
     fn test_add_role() {
         let council = vec![accounts(0), accounts(1)];
@@ -549,6 +1027,74 @@ mod tests {
         assert_eq!(1, policy.roles.len());
     }
 
+    #[test]
+    fn test_validate_policy() {
+        let mut policy = default_policy(vec![accounts(0)]);
+        assert!(policy.validate(false).is_empty());
+
+        let council = &mut policy.roles[1];
+        council
+            .permissions
+            .insert("transfers:VoteApprove".to_string());
+        council.permissions.insert("transfer:Vote".to_string());
+        council.permissions.insert("transfer".to_string());
+        council.vote_policy.insert(
+            "config".to_string(),
+            VotePolicy {
+                weight_kind: WeightKind::RoleWeight,
+                quorum: U128(2),
+                threshold: WeightOrRatio::Ratio(1, std::num::NonZeroU64::new(2).unwrap()),
+            },
+        );
+        council.vote_policy.insert(
+            "transfers".to_string(),
+            VotePolicy {
+                weight_kind: WeightKind::TokenWeight,
+                ..VotePolicy::default()
+            },
+        );
+        let mut empty = council.clone();
+        empty.kind = RoleKind::Group(HashSet::default());
+        empty.permissions.clear();
+        empty.vote_policy.clear();
+        policy.roles.push(empty);
+
+        let findings = policy.validate(false);
+        let role = || "council".to_string();
+        for finding in [
+            PolicyFinding::UnknownProposalKind {
+                role: role(),
+                label: "transfers".to_string(),
+            },
+            PolicyFinding::UnknownAction {
+                role: role(),
+                action: "Vote".to_string(),
+            },
+            PolicyFinding::InvalidPermission {
+                role: role(),
+                permission: "transfer".to_string(),
+            },
+            PolicyFinding::UnknownVotePolicyLabel {
+                role: role(),
+                label: "transfers".to_string(),
+            },
+            PolicyFinding::TokenWeightWithoutStaking {
+                role: Some(role()),
+                label: Some("transfers".to_string()),
+            },
+            PolicyFinding::UnreachableThreshold {
+                role: role(),
+                label: Some("config".to_string()),
+            },
+            PolicyFinding::DuplicateRole { role: role() },
+            PolicyFinding::EmptyGroup { role: role() },
+        ] {
+            assert!(findings.contains(&finding), "{:?}", finding);
+        }
+        assert_eq!(findings.len(), 8);
+        assert_eq!(policy.validate(true).len(), 7);
+    }
+
     #[test]
     fn test_update_default_vote_policy() {
         let council = vec![accounts(0), accounts(1)];
@@ -605,6 +1151,15 @@ mod tests {
             proposal_period: None,
             bounty_bond: None,
             bounty_forgiveness_period: Some(U64::from(1_000_000_000 * 60 * 60 * 24 * 5)),
+            proposal_bond_overrides: vec![(
+                "vote".to_string(),
+                Some(NearToken::from_millinear(100)),
+            )]
+            .into_iter()
+            .collect(),
+            proposal_period_overrides: vec![("upgrade_self".to_string(), Some(U64::from(1)))]
+                .into_iter()
+                .collect(),
         };
         policy.update_parameters(&new_parameters);
         assert_eq!(NearToken::from_near(100), policy.proposal_bond);
@@ -617,5 +1172,26 @@ mod tests {
             U64::from(1_000_000_000 * 60 * 60 * 24 * 5),
             policy.bounty_forgiveness_period
         );
+        assert_eq!(
+            NearToken::from_millinear(100),
+            policy.get_proposal_bond("vote")
+        );
+        assert_eq!(
+            NearToken::from_near(100),
+            policy.get_proposal_bond("transfer")
+        );
+        assert_eq!(1, policy.get_proposal_period("upgrade_self"));
+
+        // Overrides are removed with `null`.
+        policy.update_parameters(&PolicyParameters {
+            proposal_bond: None,
+            proposal_period: None,
+            bounty_bond: None,
+            bounty_forgiveness_period: None,
+            proposal_bond_overrides: vec![("vote".to_string(), None)].into_iter().collect(),
+            proposal_period_overrides: HashMap::default(),
+        });
+        assert_eq!(NearToken::from_near(100), policy.get_proposal_bond("vote"));
+        assert_eq!(1, policy.get_proposal_period("upgrade_self"));
     }
 }
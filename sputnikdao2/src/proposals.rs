@@ -1,14 +1,18 @@
 use std::collections::{HashMap, VecDeque};
 
 use ext_fungible_token::ext_fungible_token;
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_contract_standards::storage_management::StorageBalance;
 use near_sdk::json_types::{Base64VecU8, U64, U128};
-use near_sdk::{AccountId, Gas, NearToken, PromiseOrValue, log, utils};
+use near_sdk::{AccountId, Gas, NearToken, PromiseError, PromiseOrValue, log, utils};
 
-use crate::action_log::ProposalLog;
-use crate::policy::UserInfo;
+use crate::action_log::{LoggedAction, ProposalLog};
+use crate::policy::{TokenBond, UserInfo, VersionedPolicyV1, WeightKind};
 use crate::types::{
-    Action, Config, GAS_FOR_FT_TRANSFER, OLD_BASE_TOKEN, ONE_YOCTO_NEAR, OldAccountId,
-    convert_old_to_new_token,
+    Action, Config, GAS_FOR_CALL_CHAIN_STEP, GAS_FOR_FT_STORAGE_CALLBACK, GAS_FOR_FT_TRANSFER,
+    GAS_FOR_GET_POLICY, GAS_FOR_HUB_POLICY_CALLBACK, GAS_FOR_MOVE_TO_HUB,
+    GAS_FOR_STORAGE_BALANCE_OF, GAS_FOR_STORAGE_DEPOSIT, MAX_BOOL_RESULT_LEN, MAX_U128_RESULT_LEN,
+    OLD_BASE_TOKEN, ONE_YOCTO_NEAR, OldAccountId, convert_old_to_new_token,
 };
 use crate::upgrade::{upgrade_remote, upgrade_using_factory};
 use crate::*;
@@ -31,6 +35,14 @@ pub enum ProposalStatus {
     Moved,
     /// If proposal has failed when finalizing. Allowed to re-finalize again to either expire or approved.
     Failed,
+    /// If quorum voted yes, but the policy requires a delay before execution.
+    /// Anyone can execute it once the delay has passed, until then it can be cancelled.
+    Queued,
+    /// If proposal was vetoed by a role with veto permission. Bond is returned.
+    Vetoed,
+    /// If quorum voted yes, but the proposals it depends on are not executed yet.
+    /// Can be finalized to get executed once they are.
+    Pending,
 }
 
 /// Function call arguments.
@@ -46,6 +58,25 @@ pub struct ActionCall {
     gas: Gas,
 }
 
+impl ActionCall {
+    /// Returns total gas required for the given actions.
+    fn total_gas(actions: &[ActionCall]) -> Gas {
+        actions.iter().fold(Gas::from_gas(0), |total, action| {
+            total.saturating_add(action.gas)
+        })
+    }
+}
+
+/// Calls to a single receiver, executed as one step of the function call chain.
+#[derive(PartialEq, Clone)]
+#[near(serializers=[borsh, json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(deny_unknown_fields)]
+pub struct FunctionCallStep {
+    pub receiver_id: AccountId,
+    pub actions: Vec<ActionCall>,
+}
+
 /// Function call arguments.
 
 #[derive(PartialEq, Clone)]
@@ -57,6 +88,36 @@ pub struct PolicyParameters {
     pub proposal_period: Option<U64>,
     pub bounty_bond: Option<NearToken>,
     pub bounty_forgiveness_period: Option<U64>,
+    /// Per proposal kind bond overrides to set. `None` removes the override for given kind.
+    #[serde(default)]
+    pub proposal_bond_overrides: HashMap<String, Option<NearToken>>,
+    /// Per proposal kind expiration period overrides to set. `None` removes the override for given kind.
+    #[serde(default)]
+    pub proposal_period_overrides: HashMap<String, Option<U64>>,
+}
+
+/// Policy parameters before per proposal kind overrides were introduced. Used to read old proposals.
+#[derive(PartialEq, Clone)]
+#[near(serializers=[borsh])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct PolicyParametersV1 {
+    pub proposal_bond: Option<NearToken>,
+    pub proposal_period: Option<U64>,
+    pub bounty_bond: Option<NearToken>,
+    pub bounty_forgiveness_period: Option<U64>,
+}
+
+impl From<PolicyParametersV1> for PolicyParameters {
+    fn from(v1: PolicyParametersV1) -> Self {
+        PolicyParameters {
+            proposal_bond: v1.proposal_bond,
+            proposal_period: v1.proposal_period,
+            bounty_bond: v1.bounty_bond,
+            bounty_forgiveness_period: v1.bounty_forgiveness_period,
+            proposal_bond_overrides: HashMap::default(),
+            proposal_period_overrides: HashMap::default(),
+        }
+    }
 }
 
 /// Kinds of proposals, doing different action.
@@ -79,7 +140,8 @@ pub enum ProposalKind {
         receiver_id: AccountId,
         actions: Vec<ActionCall>,
     },
-    /// Upgrade this contract with given hash from blob store.
+    /// Upgrade this contract with the code of given hash, which the factory pulls from its own storage.
+    /// The hash isn't validated when the proposal is added, as the DAO can't read the factory storage.
     UpgradeSelf { hash: Base58CryptoHash },
     /// Upgrade another contract, by calling method with the code from given hash from blob store.
     UpgradeRemote {
@@ -118,9 +180,119 @@ pub enum ProposalKind {
     ChangePolicyUpdateDefaultVotePolicy { vote_policy: VotePolicy },
     /// Update the parameters from the policy. This is short cut to updating the whole policy.
     ChangePolicyUpdateParameters { parameters: PolicyParameters },
+    /// Executes given proposal kinds in order under a single vote.
+    /// Fails if any of the promises created by the inner proposal kinds fails.
+    Batch { kinds: Vec<ProposalKind> },
+    /// Calls receivers of the given steps one after another, each step in a separate promise.
+    /// Next step is executed only if the previous one succeeded.
+    FunctionCallChain { steps: Vec<FunctionCallStep> },
+    /// Creates a schedule paying `amount` of `token_id` to `receiver_id` for each of `periods` periods.
+    /// Accrued amount is paid out with `claim_recurring_payment`.
+    RecurringPayment {
+        /// Can be "" for $NEAR or a valid account id.
+        token_id: OldAccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        /// Length of a period in nanoseconds.
+        period: U64,
+        periods: u32,
+    },
+    /// Stops given recurring payment schedule. Amount accrued but not claimed yet is not paid out.
+    CancelRecurringPayment { schedule_id: u64 },
+    /// Stakes given amount of $NEAR in the validator staking pool.
+    StakeToPool { pool_id: AccountId, amount: U128 },
+    /// Unstakes given amount of $NEAR in the validator staking pool.
+    /// It can be withdrawn after the pool's unstaking period.
+    UnstakeFromPool { pool_id: AccountId, amount: U128 },
+    /// Withdraws given amount of unstaked $NEAR from the validator staking pool.
+    WithdrawFromPool { pool_id: AccountId, amount: U128 },
+    /// Creates sub-DAO `<name>.<factory>` through the factory of this DAO, attaching `deposit`.
+    /// If `parent_role` is set, this DAO is added to that group role of the sub-DAO's policy.
+    CreateSubDao {
+        name: AccountId,
+        config: Config,
+        policy: VersionedPolicy,
+        deposit: U128,
+        parent_role: Option<String>,
+    },
+    /// Transfers given NFT from this DAO to `receiver_id`.
+    /// If `msg` is not None, calls `nft_transfer_call` with given `msg`.
+    /// For `nft_transfer` and `nft_transfer_call` `memo` is the `description` of the proposal.
+    NftTransfer {
+        nft_contract_id: AccountId,
+        token_id: String,
+        receiver_id: AccountId,
+        msg: Option<String>,
+    },
+    /// Transfers given amounts of the `NEP-245` multi tokens from this DAO to `receiver_id` in a single batch.
+    /// If `msg` is not None, calls `mt_batch_transfer_call` with given `msg`.
+    /// For `mt_batch_transfer` and `mt_batch_transfer_call` `memo` is the `description` of the proposal.
+    MtTransfer {
+        mt_contract_id: AccountId,
+        receiver_id: AccountId,
+        /// Pairs of token id and amount.
+        tokens: Vec<(String, U128)>,
+        msg: Option<String>,
+    },
+    /// Allows `spender` to pay out up to `amount` of `token_id` per `period` with `spend_allowance`,
+    /// until `expires_at`. Replaces the allowance the spender already has for the token.
+    GrantAllowance {
+        spender: AccountId,
+        /// Can be "" for $NEAR or a valid account id.
+        token_id: OldAccountId,
+        amount: U128,
+        /// Length of a period in nanoseconds.
+        period: U64,
+        expires_at: Option<U64>,
+    },
+    /// Revokes given spending allowance.
+    RevokeAllowance { allowance_id: u64 },
+    /// Transfers given amounts of `token_id` from this DAO to each of the receivers.
+    /// Each payment is a separate promise, so some of them can fail while the others succeed.
+    /// Receivers must be unique. `memo` is the `description` of the proposal.
+    BatchTransfer {
+        /// Can be "" for $NEAR or a valid account id.
+        token_id: OldAccountId,
+        /// Pairs of receiver and amount.
+        payments: Vec<(AccountId, U128)>,
+    },
 }
 
 impl ProposalKind {
+    /// Labels of all the proposal kinds, as used in the policy.
+    pub const LABELS: &[&str] = &[
+        "config",
+        "policy",
+        "add_member_to_role",
+        "remove_member_from_role",
+        "call",
+        "upgrade_self",
+        "upgrade_remote",
+        "transfer",
+        "set_vote_token",
+        "add_bounty",
+        "bounty_done",
+        "vote",
+        "factory_info_update",
+        "policy_add_or_update_role",
+        "policy_remove_role",
+        "policy_update_default_vote_policy",
+        "policy_update_parameters",
+        "batch",
+        "call_chain",
+        "recurring_payment",
+        "cancel_recurring_payment",
+        "stake_to_pool",
+        "unstake_from_pool",
+        "withdraw_from_pool",
+        "create_sub_dao",
+        "nft_transfer",
+        "mt_transfer",
+        "grant_allowance",
+        "revoke_allowance",
+        "batch_transfer",
+    ];
+
     /// Returns label of policy for given type of proposal.
     pub fn to_policy_label(&self) -> &str {
         match self {
@@ -143,6 +315,160 @@ impl ProposalKind {
                 "policy_update_default_vote_policy"
             }
             ProposalKind::ChangePolicyUpdateParameters { .. } => "policy_update_parameters",
+            ProposalKind::Batch { .. } => "batch",
+            ProposalKind::FunctionCallChain { .. } => "call_chain",
+            ProposalKind::RecurringPayment { .. } => "recurring_payment",
+            ProposalKind::CancelRecurringPayment { .. } => "cancel_recurring_payment",
+            ProposalKind::StakeToPool { .. } => "stake_to_pool",
+            ProposalKind::UnstakeFromPool { .. } => "unstake_from_pool",
+            ProposalKind::WithdrawFromPool { .. } => "withdraw_from_pool",
+            ProposalKind::CreateSubDao { .. } => "create_sub_dao",
+            ProposalKind::NftTransfer { .. } => "nft_transfer",
+            ProposalKind::MtTransfer { .. } => "mt_transfer",
+            ProposalKind::GrantAllowance { .. } => "grant_allowance",
+            ProposalKind::RevokeAllowance { .. } => "revoke_allowance",
+            ProposalKind::BatchTransfer { .. } => "batch_transfer",
+        }
+    }
+}
+
+/// Kinds of proposals before the policy layout changed. Used to read old proposals.
+/// Must not be changed, as it embeds the old policy and policy parameters.
+#[derive(PartialEq, Clone)]
+#[near(serializers=[borsh])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[allow(clippy::large_enum_variant)]
+pub enum ProposalKindV1 {
+    ChangeConfig {
+        config: Config,
+    },
+    ChangePolicy {
+        policy: VersionedPolicyV1,
+    },
+    AddMemberToRole {
+        member_id: AccountId,
+        role: String,
+    },
+    RemoveMemberFromRole {
+        member_id: AccountId,
+        role: String,
+    },
+    FunctionCall {
+        receiver_id: AccountId,
+        actions: Vec<ActionCall>,
+    },
+    UpgradeSelf {
+        hash: Base58CryptoHash,
+    },
+    UpgradeRemote {
+        receiver_id: AccountId,
+        method_name: String,
+        hash: Base58CryptoHash,
+    },
+    Transfer {
+        token_id: OldAccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        msg: Option<String>,
+    },
+    SetStakingContract {
+        staking_id: AccountId,
+    },
+    AddBounty {
+        bounty: Bounty,
+    },
+    BountyDone {
+        bounty_id: u64,
+        receiver_id: AccountId,
+    },
+    Vote,
+    FactoryInfoUpdate {
+        factory_info: FactoryInfo,
+    },
+    ChangePolicyAddOrUpdateRole {
+        role: RolePermission,
+    },
+    ChangePolicyRemoveRole {
+        role: String,
+    },
+    ChangePolicyUpdateDefaultVotePolicy {
+        vote_policy: VotePolicy,
+    },
+    ChangePolicyUpdateParameters {
+        parameters: PolicyParametersV1,
+    },
+}
+
+impl From<ProposalKindV1> for ProposalKind {
+    fn from(v1: ProposalKindV1) -> Self {
+        match v1 {
+            ProposalKindV1::ChangeConfig { config } => ProposalKind::ChangeConfig { config },
+            ProposalKindV1::ChangePolicy { policy } => ProposalKind::ChangePolicy {
+                policy: policy.into(),
+            },
+            ProposalKindV1::AddMemberToRole { member_id, role } => {
+                ProposalKind::AddMemberToRole { member_id, role }
+            }
+            ProposalKindV1::RemoveMemberFromRole { member_id, role } => {
+                ProposalKind::RemoveMemberFromRole { member_id, role }
+            }
+            ProposalKindV1::FunctionCall {
+                receiver_id,
+                actions,
+            } => ProposalKind::FunctionCall {
+                receiver_id,
+                actions,
+            },
+            ProposalKindV1::UpgradeSelf { hash } => ProposalKind::UpgradeSelf { hash },
+            ProposalKindV1::UpgradeRemote {
+                receiver_id,
+                method_name,
+                hash,
+            } => ProposalKind::UpgradeRemote {
+                receiver_id,
+                method_name,
+                hash,
+            },
+            ProposalKindV1::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+            } => ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+            },
+            ProposalKindV1::SetStakingContract { staking_id } => {
+                ProposalKind::SetStakingContract { staking_id }
+            }
+            ProposalKindV1::AddBounty { bounty } => ProposalKind::AddBounty { bounty },
+            ProposalKindV1::BountyDone {
+                bounty_id,
+                receiver_id,
+            } => ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+            },
+            ProposalKindV1::Vote => ProposalKind::Vote,
+            ProposalKindV1::FactoryInfoUpdate { factory_info } => {
+                ProposalKind::FactoryInfoUpdate { factory_info }
+            }
+            ProposalKindV1::ChangePolicyAddOrUpdateRole { role } => {
+                ProposalKind::ChangePolicyAddOrUpdateRole { role }
+            }
+            ProposalKindV1::ChangePolicyRemoveRole { role } => {
+                ProposalKind::ChangePolicyRemoveRole { role }
+            }
+            ProposalKindV1::ChangePolicyUpdateDefaultVotePolicy { vote_policy } => {
+                ProposalKind::ChangePolicyUpdateDefaultVotePolicy { vote_policy }
+            }
+            ProposalKindV1::ChangePolicyUpdateParameters { parameters } => {
+                ProposalKind::ChangePolicyUpdateParameters {
+                    parameters: parameters.into(),
+                }
+            }
         }
     }
 }
@@ -168,17 +494,36 @@ impl From<Action> for Vote {
 }
 
 /// Proposal that are sent to this DAO.
-#[near(serializers=[borsh, json])]
+#[near(serializers=[borsh])]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
 #[derive(Clone)]
-#[serde(deny_unknown_fields)]
 pub struct ProposalV1 {
     /// Original proposer.
     pub proposer: AccountId,
     /// Description of this proposal.
     pub description: String,
     /// Kind of proposal with relevant information.
-    pub kind: ProposalKind,
+    pub kind: ProposalKindV1,
+    /// Current status of the proposal.
+    pub status: ProposalStatus,
+    /// Count of votes per role per decision: yes / no / spam.
+    pub vote_counts: HashMap<String, [U128; 3]>,
+    /// Map of who voted and how.
+    pub votes: HashMap<AccountId, Vote>,
+    /// Submission time (for voting period).
+    pub submission_time: U64,
+}
+
+#[near(serializers=[borsh])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[derive(Clone)]
+pub struct ProposalV2 {
+    /// Original proposer.
+    pub proposer: AccountId,
+    /// Description of this proposal.
+    pub description: String,
+    /// Kind of proposal with relevant information.
+    pub kind: ProposalKindV1,
     /// Current status of the proposal.
     pub status: ProposalStatus,
     /// Count of votes per role per decision: yes / no / spam.
@@ -187,6 +532,8 @@ pub struct ProposalV1 {
     pub votes: HashMap<AccountId, Vote>,
     /// Submission time (for voting period).
     pub submission_time: U64,
+    /// Last actions log
+    pub last_actions_log: VecDeque<ProposalLog>,
 }
 
 #[near(serializers=[borsh, json])]
@@ -209,19 +556,120 @@ pub struct Proposal {
     pub submission_time: U64,
     /// Last actions log
     pub last_actions_log: VecDeque<ProposalLog>,
+    /// Time when the proposal got approved and queued for execution (for execution delay).
+    pub queued_at: Option<U64>,
+    /// Bond paid in fungible token instead of NEAR, returned in the same token.
+    pub token_bond: Option<TokenBond>,
+    /// Proposals that must be executed before this one.
+    pub depends_on: Vec<u64>,
+    /// Proposal in the hub DAO this proposal was moved to.
+    pub moved_to: Option<RemoteProposal>,
+    /// Details of the failed execution, if the proposal has failed.
+    pub failure: Option<ProposalFailure>,
+    /// Receivers of the `BatchTransfer` payments that have succeeded.
+    /// These are not paid again when the failed proposal is finalized again.
+    pub completed_payments: Vec<AccountId>,
+    /// Indexes of the inner kinds of the `Batch` that have succeeded.
+    /// These are not executed again when the failed proposal is finalized again.
+    pub completed_kinds: Vec<u64>,
+    /// Whether the execution of the approved proposal has been confirmed,
+    /// right away or by the callback once all its promises have succeeded.
+    pub executed: bool,
+    /// Time when the proposal got finalized: executed, rejected, vetoed, expired or moved to the hub.
+    pub finalized_at: Option<U64>,
+}
+
+/// Details of the failed proposal execution.
+#[derive(Clone, PartialEq)]
+#[near(serializers=[borsh, json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct ProposalFailure {
+    /// Block height of the last failure.
+    pub block_height: U64,
+    /// How many times the execution has failed.
+    pub count: u32,
+    /// Indexes of the promise results that failed in the last execution,
+    /// e.g. of the inner kinds of a batch proposal or of the pending payments of a batch transfer.
+    pub failed_promises: Vec<u64>,
+}
+
+/// Compact summary of a finalized proposal that replaces it once pruned.
+#[derive(Clone, PartialEq)]
+#[near(serializers=[borsh, json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct ProposalSummary {
+    /// Original proposer.
+    pub proposer: AccountId,
+    /// Label of the proposal kind.
+    pub kind: String,
+    /// Final status of the proposal.
+    pub status: ProposalStatus,
+    /// Final count of votes per role per decision: yes / no / spam.
+    pub vote_counts: HashMap<String, [U128; 3]>,
+    /// Proposal in the hub DAO this proposal was moved to.
+    pub moved_to: Option<RemoteProposal>,
+    /// Details of the last failed execution, if the execution has ever failed.
+    pub failure: Option<ProposalFailure>,
+    /// Sha256 hash of the full borsh serialized proposal record.
+    pub hash: Base58CryptoHash,
+}
+
+/// Reference to a proposal in another DAO.
+#[derive(Clone, PartialEq)]
+#[near(serializers=[borsh, json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct RemoteProposal {
+    pub dao_id: AccountId,
+    pub proposal_id: u64,
 }
 
 impl From<ProposalV1> for Proposal {
     fn from(v1: ProposalV1) -> Self {
+        // Old proposals were approved once executed, failed executions went to `Failed`.
+        let executed = v1.status == ProposalStatus::Approved;
         Proposal {
             proposer: v1.proposer.clone(),
             description: v1.description.clone(),
-            kind: v1.kind.clone(),
+            kind: v1.kind.clone().into(),
             status: v1.status.clone(),
             vote_counts: v1.vote_counts.clone(),
             votes: v1.votes.clone(),
             submission_time: v1.submission_time,
             last_actions_log: VecDeque::new(),
+            queued_at: None,
+            token_bond: None,
+            depends_on: vec![],
+            moved_to: None,
+            failure: None,
+            completed_payments: vec![],
+            completed_kinds: vec![],
+            executed,
+            finalized_at: None,
+        }
+    }
+}
+
+impl From<ProposalV2> for Proposal {
+    fn from(v2: ProposalV2) -> Self {
+        let executed = v2.status == ProposalStatus::Approved;
+        Proposal {
+            proposer: v2.proposer,
+            description: v2.description,
+            kind: v2.kind.into(),
+            status: v2.status,
+            vote_counts: v2.vote_counts,
+            votes: v2.votes,
+            submission_time: v2.submission_time,
+            last_actions_log: v2.last_actions_log,
+            queued_at: None,
+            token_bond: None,
+            depends_on: vec![],
+            moved_to: None,
+            failure: None,
+            completed_payments: vec![],
+            completed_kinds: vec![],
+            executed,
+            finalized_at: None,
         }
     }
 }
@@ -229,8 +677,11 @@ impl From<ProposalV1> for Proposal {
 #[derive(Clone)]
 #[near(serializers=[borsh])]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[allow(clippy::large_enum_variant)]
 pub enum VersionedProposal {
     V1(ProposalV1),
+    /// Layout of the deployed `Latest` before the execution delay was introduced.
+    V2(ProposalV2),
     Latest(Proposal),
 }
 
@@ -247,12 +698,59 @@ impl From<VersionedProposal> for Proposal {
     fn from(v: VersionedProposal) -> Self {
         match v {
             VersionedProposal::V1(p) => p.into(),
+            VersionedProposal::V2(p) => p.into(),
             VersionedProposal::Latest(p) => p,
         }
     }
 }
 
 impl Proposal {
+    /// Returns true if the proposal can't change anymore: executed, rejected, vetoed, expired or moved to the hub.
+    fn is_finalized(&self) -> bool {
+        match self.status {
+            ProposalStatus::Approved => self.executed,
+            ProposalStatus::Moved => self.moved_to.is_some(),
+            ProposalStatus::Rejected | ProposalStatus::Vetoed | ProposalStatus::Expired => true,
+            _ => false,
+        }
+    }
+
+    /// Records the time when the proposal got finalized, once it is.
+    pub(crate) fn update_finalized_at(&mut self) {
+        if self.finalized_at.is_none() && self.is_finalized() {
+            self.finalized_at = Some(U64::from(env::block_timestamp()));
+        }
+    }
+
+    /// Returns the time when the proposal got finalized, if it is.
+    /// Proposals finalized before the time was recorded fall back to their submission time.
+    fn finalization_time(&self) -> Option<u64> {
+        self.is_finalized()
+            .then(|| self.finalized_at.unwrap_or(self.submission_time).0)
+    }
+
+    /// Payments of the batch transfer that have not succeeded yet.
+    pub(crate) fn pending_payments(&self) -> Vec<(AccountId, U128)> {
+        match &self.kind {
+            ProposalKind::BatchTransfer { payments, .. } => payments
+                .iter()
+                .filter(|(receiver_id, _)| !self.completed_payments.contains(receiver_id))
+                .cloned()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Indexes of the inner kinds of the batch that have not succeeded yet.
+    pub(crate) fn pending_kinds(&self) -> Vec<u64> {
+        match &self.kind {
+            ProposalKind::Batch { kinds } => (0..kinds.len() as u64)
+                .filter(|index| !self.completed_kinds.contains(index))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     pub fn update_votes(
         &mut self,
         account_id: &AccountId,
@@ -262,12 +760,12 @@ impl Proposal {
         user_weight: Balance,
     ) {
         for role in roles {
-            let amount = if policy.is_token_weighted(role, &self.kind.to_policy_label().to_string())
-            {
-                user_weight
-            } else {
-                1
-            };
+            let amount =
+                match policy.get_weight_kind(role, &self.kind.to_policy_label().to_string()) {
+                    WeightKind::TokenWeight => user_weight,
+                    WeightKind::QuadraticTokenWeight => user_weight.isqrt(),
+                    WeightKind::RoleWeight => 1,
+                };
             let defaults = [U128::from(0); 3];
             let vote_counted =
                 self.vote_counts.entry(role.clone()).or_insert(defaults)[vote.clone() as usize].0
@@ -289,6 +787,9 @@ pub struct ProposalInput {
     pub description: String,
     /// Kind of proposal with relevant information.
     pub kind: ProposalKind,
+    /// Proposals that must be executed before this one.
+    #[serde(default)]
+    pub depends_on: Vec<u64>,
 }
 
 impl From<ProposalInput> for VersionedProposal {
@@ -302,10 +803,33 @@ impl From<ProposalInput> for VersionedProposal {
             votes: HashMap::default(),
             submission_time: U64::from(env::block_timestamp()),
             last_actions_log: VecDeque::new(),
+            queued_at: None,
+            token_bond: None,
+            depends_on: input.depends_on,
+            moved_to: None,
+            failure: None,
+            completed_payments: vec![],
+            completed_kinds: vec![],
+            executed: false,
+            finalized_at: None,
         })
     }
 }
 
+/// Calls `receiver_id` with list of actions in a single promise.
+fn internal_function_call(receiver_id: &AccountId, actions: &[ActionCall]) -> Promise {
+    let mut promise = Promise::new(receiver_id.clone());
+    for action in actions {
+        promise = promise.function_call(
+            action.method_name.clone(),
+            action.args.clone(),
+            action.deposit,
+            action.gas,
+        )
+    }
+    promise
+}
+
 impl Contract {
     /// Execute payout of given token to given user.
     pub(crate) fn internal_payout(
@@ -316,23 +840,65 @@ impl Contract {
         memo: String,
         msg: Option<String>,
     ) -> PromiseOrValue<()> {
-        if token_id.is_none() {
-            Promise::new(receiver_id.clone())
+        let Some(token_id) = token_id else {
+            return Promise::new(receiver_id.clone())
                 .transfer(NearToken::from_yoctonear(amount))
-                .into()
+                .into();
+        };
+        if self
+            .policy
+            .get()
+            .unwrap()
+            .to_policy()
+            .ft_storage_deposit
+            .is_none()
+        {
+            return Self::internal_ft_transfer(token_id, receiver_id, amount, memo, msg).into();
+        }
+        // Storage of the receiver is checked first, the transfer is scheduled by the callback.
+        ext_fungible_token::ext(token_id.clone())
+            .with_static_gas(GAS_FOR_STORAGE_BALANCE_OF)
+            .storage_balance_of(receiver_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_FT_STORAGE_CALLBACK)
+                    .on_ft_storage_balance_callback(
+                        token_id.clone(),
+                        receiver_id.clone(),
+                        U128(amount),
+                        memo,
+                        msg,
+                    ),
+            )
+            .into()
+    }
+
+    /// Transfers given amount of the fungible token to the receiver.
+    /// If `msg` is not None, calls `ft_transfer_call` with given `msg`.
+    pub(crate) fn internal_ft_transfer(
+        token_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: String,
+        msg: Option<String>,
+    ) -> Promise {
+        let ft = ext_fungible_token::ext(token_id.clone())
+            .with_attached_deposit(ONE_YOCTO_NEAR)
+            .with_static_gas(GAS_FOR_FT_TRANSFER);
+        if let Some(msg) = msg {
+            ft.ft_transfer_call(receiver_id.clone(), U128(amount), Some(memo), msg)
         } else {
-            if let Some(msg) = msg {
-                ext_fungible_token::ext(token_id.as_ref().unwrap().clone())
-                    .with_attached_deposit(ONE_YOCTO_NEAR)
-                    .with_static_gas(GAS_FOR_FT_TRANSFER)
-                    .ft_transfer_call(receiver_id.clone(), U128(amount), Some(memo), msg)
-            } else {
-                ext_fungible_token::ext(token_id.as_ref().unwrap().clone())
-                    .with_attached_deposit(ONE_YOCTO_NEAR)
-                    .with_static_gas(GAS_FOR_FT_TRANSFER)
-                    .ft_transfer(receiver_id.clone(), U128(amount), Some(memo))
+            ft.ft_transfer(receiver_id.clone(), U128(amount), Some(memo))
+        }
+    }
+
+    /// Debits tracked balance of given fungible token after it has been paid out.
+    pub(crate) fn internal_debit_token(&mut self, token_id: &OldAccountId, amount: Balance) {
+        if let Some(token_id) = convert_old_to_new_token(token_id) {
+            if let Some(balance) = self.token_balances.get(&token_id) {
+                self.token_balances
+                    .insert(&token_id, &balance.saturating_sub(amount));
             }
-            .into()
         }
     }
 
@@ -344,18 +910,70 @@ impl Contract {
                 .detach();
         }
 
-        self.locked_amount = self.locked_amount.saturating_sub(policy.proposal_bond);
-        Promise::new(proposal.proposer.clone()).transfer(policy.proposal_bond)
+        if let Some(token_bond) = &proposal.token_bond {
+            if let Some(locked) = self.locked_token_amounts.get_mut(&token_bond.token_id) {
+                *locked = locked.saturating_sub(token_bond.amount.0);
+            }
+            return ext_fungible_token::ext(token_bond.token_id.clone())
+                .with_attached_deposit(ONE_YOCTO_NEAR)
+                .with_static_gas(GAS_FOR_FT_TRANSFER)
+                .ft_transfer(proposal.proposer.clone(), token_bond.amount, None);
+        }
+
+        let proposal_bond = policy.get_proposal_bond(proposal.kind.to_policy_label());
+        self.locked_amount = self.locked_amount.saturating_sub(proposal_bond);
+        Promise::new(proposal.proposer.clone()).transfer(proposal_bond)
     }
 
     /// Executes given proposal and updates the contract's state.
     fn internal_execute_proposal(
         &mut self,
         policy: &Policy,
-        proposal: &Proposal,
+        proposal: &mut Proposal,
         proposal_id: u64,
     ) -> PromiseOrValue<()> {
         let result = match &proposal.kind {
+            // Payments completed by the previous executions are not repeated.
+            ProposalKind::BatchTransfer { token_id, .. } => self.internal_batch_transfer(
+                token_id,
+                &proposal.pending_payments(),
+                &proposal.description,
+            ),
+            // Inner kinds completed by the previous executions are not repeated.
+            ProposalKind::Batch { kinds } => {
+                let (result, completed_kinds) = self.internal_execute_batch(
+                    kinds,
+                    &proposal.pending_kinds(),
+                    &proposal.description,
+                );
+                proposal.completed_kinds.extend(completed_kinds);
+                result
+            }
+            kind => self.internal_execute_proposal_kind(policy, kind, &proposal.description),
+        };
+        match result {
+            PromiseOrValue::Promise(promise) => promise
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_FT_TRANSFER)
+                        .on_proposal_callback(proposal_id),
+                )
+                .into(),
+            PromiseOrValue::Value(()) => {
+                proposal.executed = true;
+                self.internal_return_bonds(policy, proposal).into()
+            }
+        }
+    }
+
+    /// Executes given kind of proposal. Returns promise if execution requires cross-contract calls.
+    fn internal_execute_proposal_kind(
+        &mut self,
+        policy: &Policy,
+        kind: &ProposalKind,
+        description: &str,
+    ) -> PromiseOrValue<()> {
+        match kind {
             ProposalKind::ChangeConfig { config } => {
                 self.config.set(config);
                 PromiseOrValue::Value(())
@@ -379,18 +997,7 @@ impl Contract {
             ProposalKind::FunctionCall {
                 receiver_id,
                 actions,
-            } => {
-                let mut promise = Promise::new(receiver_id.clone());
-                for action in actions {
-                    promise = promise.function_call(
-                        action.method_name.clone(),
-                        action.args.clone(),
-                        action.deposit,
-                        action.gas,
-                    )
-                }
-                promise.into()
-            }
+            } => internal_function_call(receiver_id, actions).into(),
             ProposalKind::UpgradeSelf { hash } => {
                 upgrade_using_factory(hash);
                 PromiseOrValue::Value(())
@@ -412,34 +1019,110 @@ impl Contract {
                 &convert_old_to_new_token(token_id),
                 receiver_id,
                 amount.0,
-                proposal.description.clone(),
+                description.to_string(),
                 msg.clone(),
             ),
-            ProposalKind::SetStakingContract { staking_id } => {
-                assert!(self.staking_id.is_none(), "ERR_INVALID_STAKING_CHANGE");
-                self.staking_id = Some(staking_id.clone());
+            ProposalKind::RecurringPayment {
+                token_id,
+                receiver_id,
+                amount,
+                period,
+                periods,
+            } => {
+                self.internal_add_recurring_payment(
+                    token_id,
+                    receiver_id,
+                    *amount,
+                    *period,
+                    *periods,
+                );
                 PromiseOrValue::Value(())
             }
-            ProposalKind::AddBounty { bounty } => {
-                self.internal_add_bounty(bounty);
+            ProposalKind::CancelRecurringPayment { schedule_id } => {
+                self.recurring_payments.remove(schedule_id);
                 PromiseOrValue::Value(())
             }
-            ProposalKind::BountyDone {
-                bounty_id,
-                receiver_id,
-            } => self.internal_execute_bounty_payout(*bounty_id, receiver_id, true),
-            ProposalKind::Vote => PromiseOrValue::Value(()),
-            ProposalKind::FactoryInfoUpdate { factory_info } => {
-                internal_set_factory_info(factory_info);
+            ProposalKind::GrantAllowance {
+                spender,
+                token_id,
+                amount,
+                period,
+                expires_at,
+            } => {
+                self.internal_add_allowance(spender, token_id, *amount, *period, *expires_at);
                 PromiseOrValue::Value(())
             }
-            ProposalKind::ChangePolicyAddOrUpdateRole { role } => {
-                let mut new_policy = policy.clone();
-                new_policy.add_or_update_role(role);
-                self.policy.set(&VersionedPolicy::Current(new_policy));
+            ProposalKind::RevokeAllowance { allowance_id } => {
+                self.internal_remove_allowance(*allowance_id);
                 PromiseOrValue::Value(())
             }
-            ProposalKind::ChangePolicyRemoveRole { role } => {
+            ProposalKind::StakeToPool { .. }
+            | ProposalKind::UnstakeFromPool { .. }
+            | ProposalKind::WithdrawFromPool { .. } => {
+                self.internal_execute_pool_action(kind).into()
+            }
+            ProposalKind::CreateSubDao {
+                name,
+                config,
+                policy,
+                deposit,
+                parent_role,
+            } => self
+                .internal_create_sub_dao(name, config, policy, *deposit, parent_role)
+                .into(),
+            ProposalKind::NftTransfer {
+                nft_contract_id,
+                token_id,
+                receiver_id,
+                msg,
+            } => self
+                .internal_nft_transfer(
+                    nft_contract_id,
+                    token_id,
+                    receiver_id,
+                    description.to_string(),
+                    msg.clone(),
+                )
+                .into(),
+            ProposalKind::MtTransfer {
+                mt_contract_id,
+                receiver_id,
+                tokens,
+                msg,
+            } => self
+                .internal_mt_transfer(
+                    mt_contract_id,
+                    receiver_id,
+                    tokens,
+                    description.to_string(),
+                    msg.clone(),
+                )
+                .into(),
+            ProposalKind::SetStakingContract { staking_id } => {
+                assert!(self.staking_id.is_none(), "ERR_INVALID_STAKING_CHANGE");
+                self.staking_id = Some(staking_id.clone());
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::AddBounty { bounty } => {
+                self.internal_add_bounty(bounty);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+            } => self.internal_execute_bounty_payout(*bounty_id, receiver_id, true),
+            ProposalKind::Vote => PromiseOrValue::Value(()),
+            ProposalKind::FactoryInfoUpdate { factory_info } => {
+                internal_set_factory_info(factory_info);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ChangePolicyAddOrUpdateRole { role } => {
+                let mut new_policy = policy.clone();
+                new_policy.add_or_update_role(role);
+                self.policy.set(&VersionedPolicy::Current(new_policy));
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ChangePolicyRemoveRole { role } => {
                 let mut new_policy = policy.clone();
                 new_policy.remove_role(role);
                 self.policy.set(&VersionedPolicy::Current(new_policy));
@@ -457,16 +1140,223 @@ impl Contract {
                 self.policy.set(&VersionedPolicy::Current(new_policy));
                 PromiseOrValue::Value(())
             }
-        };
-        match result {
-            PromiseOrValue::Promise(promise) => promise
-                .then(
-                    Self::ext(env::current_account_id())
-                        .with_static_gas(GAS_FOR_FT_TRANSFER)
-                        .on_proposal_callback(proposal_id),
-                )
-                .into(),
-            PromiseOrValue::Value(()) => self.internal_return_bonds(policy, proposal).into(),
+            ProposalKind::Batch { kinds } => {
+                let indexes: Vec<u64> = (0..kinds.len() as u64).collect();
+                self.internal_execute_batch(kinds, &indexes, description).0
+            }
+            ProposalKind::FunctionCallChain { steps } => {
+                Self::internal_function_call_chain(steps).into()
+            }
+            ProposalKind::BatchTransfer { token_id, payments } => {
+                self.internal_batch_transfer(token_id, payments, description)
+            }
+        }
+    }
+
+    /// Executes the inner kinds of the batch with given indexes, promises of all of them joined together.
+    /// Returns indexes of the kinds that have completed right away, without a promise.
+    fn internal_execute_batch(
+        &mut self,
+        kinds: &[ProposalKind],
+        indexes: &[u64],
+        description: &str,
+    ) -> (PromiseOrValue<()>, Vec<u64>) {
+        let mut joint_promise: Option<Promise> = None;
+        let mut completed_kinds = vec![];
+        for index in indexes {
+            // Policy can be changed by the previous kinds in the batch.
+            let policy = self.policy.get().unwrap().to_policy();
+            match self.internal_execute_proposal_kind(&policy, &kinds[*index as usize], description)
+            {
+                PromiseOrValue::Promise(promise) => {
+                    joint_promise = Some(match joint_promise {
+                        Some(joint_promise) => joint_promise.and(promise),
+                        None => promise,
+                    });
+                }
+                PromiseOrValue::Value(()) => completed_kinds.push(*index),
+            }
+        }
+        (
+            joint_promise.map_or(PromiseOrValue::Value(()), PromiseOrValue::Promise),
+            completed_kinds,
+        )
+    }
+
+    /// Pays out each of given payments in a separate promise, all joined together.
+    fn internal_batch_transfer(
+        &mut self,
+        token_id: &OldAccountId,
+        payments: &[(AccountId, U128)],
+        description: &str,
+    ) -> PromiseOrValue<()> {
+        let mut joint_promise: Option<Promise> = None;
+        for (receiver_id, amount) in payments {
+            if let PromiseOrValue::Promise(promise) = self.internal_payout(
+                &convert_old_to_new_token(token_id),
+                receiver_id,
+                amount.0,
+                description.to_string(),
+                None,
+            ) {
+                joint_promise = Some(match joint_promise {
+                    Some(joint_promise) => joint_promise.and(promise),
+                    None => promise,
+                });
+            }
+        }
+        joint_promise.map_or(PromiseOrValue::Value(()), PromiseOrValue::Promise)
+    }
+
+    /// Executes the first of given steps and schedules the rest after it.
+    fn internal_function_call_chain(steps: &[FunctionCallStep]) -> Promise {
+        let (step, next_steps) = steps.split_first().expect("ERR_EMPTY_CALL_CHAIN");
+        let promise = internal_function_call(&step.receiver_id, &step.actions);
+        if next_steps.is_empty() {
+            return promise;
+        }
+        let gas = next_steps.iter().fold(Gas::from_gas(0), |total, step| {
+            total
+                .saturating_add(GAS_FOR_CALL_CHAIN_STEP)
+                .saturating_add(ActionCall::total_gas(&step.actions))
+        });
+        promise.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(gas)
+                .on_call_chain_step_callback(next_steps.to_vec()),
+        )
+    }
+
+    /// Forwards given proposal to the hub DAO of the policy, after reading the bond it requires from the hub policy.
+    /// Proposal is marked as moved right away, the callbacks record its id in the hub or revert it if adding failed.
+    fn internal_move_to_hub(
+        &self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) -> Promise {
+        let hub_id = policy.hub_id.clone().expect("ERR_NO_HUB");
+        // Bounty claims and bonds in tokens are tracked only in this DAO.
+        assert!(
+            proposal.token_bond.is_none()
+                && !matches!(proposal.kind, ProposalKind::BountyDone { .. }),
+            "ERR_PROPOSAL_CANT_BE_MOVED"
+        );
+        proposal.status = ProposalStatus::Moved;
+        ext_dao::ext(hub_id.clone())
+            .with_static_gas(GAS_FOR_GET_POLICY)
+            .get_policy()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_HUB_POLICY_CALLBACK)
+                    .on_hub_policy_callback(proposal_id, hub_id),
+            )
+    }
+
+    /// Adds given proposal to the hub with the bond the hub requires for its kind.
+    fn internal_add_to_hub(
+        &self,
+        proposal: &Proposal,
+        proposal_id: u64,
+        hub_id: AccountId,
+        bond: NearToken,
+    ) -> Promise {
+        ext_dao::ext(hub_id.clone())
+            .with_attached_deposit(bond)
+            .with_static_gas(GAS_FOR_MOVE_TO_HUB)
+            .add_proposal(ProposalInput {
+                description: format!(
+                    "{}\nMoved from {} proposal {}, proposed by {}",
+                    proposal.description,
+                    env::current_account_id(),
+                    proposal_id,
+                    proposal.proposer
+                ),
+                kind: proposal.kind.clone(),
+                depends_on: vec![],
+            })
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_FT_TRANSFER)
+                    .on_move_to_hub_callback(proposal_id, hub_id),
+            )
+    }
+
+    /// Returns `Approved` if all the proposals given proposal depends on are executed,
+    /// `Expired` if any of them can't be executed anymore and `Pending` otherwise.
+    fn internal_dependencies_status(&self, proposal: &Proposal) -> ProposalStatus {
+        let mut status = ProposalStatus::Approved;
+        for dependency_id in &proposal.depends_on {
+            // Approved proposal is executed only once its execution has been confirmed.
+            // Pruned approved proposals are counted as executed.
+            let (dependency_status, executed) = match self.proposals.get(dependency_id) {
+                Some(dependency) => {
+                    let dependency = Proposal::from(dependency);
+                    (Some(dependency.status), dependency.executed)
+                }
+                None => (
+                    self.pruned_proposals
+                        .get(dependency_id)
+                        .map(|summary| summary.status),
+                    true,
+                ),
+            };
+            match dependency_status {
+                Some(ProposalStatus::Approved) if executed => {}
+                // Removed and withdrawn proposals are deleted from the storage.
+                None
+                | Some(ProposalStatus::Rejected)
+                | Some(ProposalStatus::Removed)
+                | Some(ProposalStatus::Expired)
+                | Some(ProposalStatus::Vetoed)
+                | Some(ProposalStatus::Moved) => return ProposalStatus::Expired,
+                Some(_) => status = ProposalStatus::Pending,
+            }
+        }
+        status
+    }
+
+    /// Executes approved proposal right away or queues it if policy requires a delay before execution.
+    /// Proposals that already went through the queue (e.g. re-finalized after failure) are executed.
+    /// Proposals waiting for their dependencies stay pending, and expire if any dependency can't be executed.
+    fn internal_approve_proposal(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) {
+        proposal.status = self.internal_approved_status(policy, proposal);
+        match proposal.status {
+            ProposalStatus::Pending => {}
+            ProposalStatus::Expired => {
+                self.internal_reject_proposal(policy, proposal, true)
+                    .detach();
+            }
+            ProposalStatus::Queued => {
+                proposal.queued_at = Some(U64::from(env::block_timestamp()));
+            }
+            _ => {
+                self.internal_execute_proposal(policy, proposal, proposal_id)
+                    .detach();
+            }
+        }
+    }
+
+    /// Returns status of the approved proposal: pending or expired because of its dependencies,
+    /// queued because of the execution delay, or approved to get executed right away.
+    pub(crate) fn internal_approved_status(
+        &self,
+        policy: &Policy,
+        proposal: &Proposal,
+    ) -> ProposalStatus {
+        match self.internal_dependencies_status(proposal) {
+            ProposalStatus::Approved
+                if proposal.queued_at.is_none()
+                    && policy.get_execution_delay(proposal.kind.to_policy_label()) > 0 =>
+            {
+                ProposalStatus::Queued
+            }
+            status => status,
         }
     }
 
@@ -477,6 +1367,7 @@ impl Contract {
         let policy = self.policy.get().unwrap().to_policy();
         if let ProposalKind::BountyDone { bounty_id, .. } = proposal.kind {
             let mut bounty: Bounty = self.bounties.get(&bounty_id).expect("ERR_NO_BOUNTY").into();
+            self.internal_debit_token(&bounty.token, bounty.amount.0);
             if bounty.times == 0 {
                 self.bounties.remove(&bounty_id);
             } else {
@@ -485,15 +1376,102 @@ impl Contract {
                     .insert(&bounty_id, &VersionedBounty::Default(bounty));
             }
         }
+        self.internal_callback_proposal_kind_success(&proposal.kind, 0);
         proposal.status = ProposalStatus::Approved;
+        proposal.executed = true;
         self.internal_return_bonds(&policy, proposal).into()
     }
 
+    /// Records the outcome of the given kind, once its promise with given result index has succeeded.
+    fn internal_callback_proposal_kind_success(&mut self, kind: &ProposalKind, result_index: u64) {
+        match kind {
+            ProposalKind::StakeToPool { .. }
+            | ProposalKind::UnstakeFromPool { .. }
+            | ProposalKind::WithdrawFromPool { .. } => self.internal_update_pool_balances(kind),
+            ProposalKind::Transfer {
+                token_id,
+                amount,
+                msg,
+                ..
+            } => {
+                // `ft_transfer_call` returns the amount used by the receiver, the rest is refunded.
+                let used_amount = if msg.is_some() {
+                    env::promise_result_checked(result_index, MAX_U128_RESULT_LEN)
+                        .ok()
+                        .and_then(|value| near_sdk::serde_json::from_slice::<U128>(&value).ok())
+                        .unwrap_or(*amount)
+                } else {
+                    *amount
+                };
+                self.internal_debit_token(token_id, used_amount.0)
+            }
+            ProposalKind::CreateSubDao { name, .. } => {
+                self.sub_daos.insert(&Self::internal_sub_dao_id(name));
+            }
+            ProposalKind::NftTransfer {
+                nft_contract_id,
+                token_id,
+                msg,
+                ..
+            } => {
+                // `nft_transfer_call` returns false if the receiver has returned the NFT.
+                let returned = msg.is_some()
+                    && matches!(
+                        env::promise_result_checked(result_index, MAX_BOOL_RESULT_LEN)
+                            .ok()
+                            .and_then(|value| near_sdk::serde_json::from_slice(&value).ok()),
+                        Some(false)
+                    );
+                if !returned {
+                    self.internal_remove_nft(nft_contract_id, token_id);
+                }
+            }
+            // Inner kinds are recorded one by one as they succeed, see `internal_record_completed`.
+            _ => {}
+        }
+    }
+
+    /// Records the payments of the batch transfer or the inner kinds of the batch
+    /// that have succeeded in the last execution.
+    /// Results are in the order of the payments or the kinds that were pending before it.
+    fn internal_record_completed(&mut self, proposal: &mut Proposal, failed_promises: &[u64]) {
+        match &proposal.kind {
+            ProposalKind::BatchTransfer { token_id, .. } => {
+                for (index, (receiver_id, amount)) in
+                    proposal.pending_payments().into_iter().enumerate()
+                {
+                    if !failed_promises.contains(&(index as u64)) {
+                        self.internal_debit_token(token_id, amount.0);
+                        proposal.completed_payments.push(receiver_id);
+                    }
+                }
+            }
+            ProposalKind::Batch { kinds } => {
+                for (index, kind_index) in proposal.pending_kinds().into_iter().enumerate() {
+                    if !failed_promises.contains(&(index as u64)) {
+                        self.internal_callback_proposal_kind_success(
+                            &kinds[kind_index as usize],
+                            index as u64,
+                        );
+                        proposal.completed_kinds.push(kind_index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
     pub(crate) fn internal_callback_proposal_fail(
         &mut self,
         proposal: &mut Proposal,
+        failed_promises: Vec<u64>,
     ) -> PromiseOrValue<()> {
         proposal.status = ProposalStatus::Failed;
+        proposal.failure = Some(ProposalFailure {
+            block_height: env::block_height().into(),
+            count: proposal.failure.as_ref().map_or(0, |failure| failure.count) + 1,
+            failed_promises,
+        });
         PromiseOrValue::Value(())
     }
 
@@ -519,36 +1497,57 @@ impl Contract {
         }
     }
 
-    pub(crate) fn internal_user_info(&self) -> UserInfo {
-        let account_id = env::predecessor_account_id();
-        UserInfo {
-            amount: self.get_user_weight(&account_id),
-            account_id,
-        }
-    }
-}
-
-#[near]
-impl Contract {
-    /// Add proposal to this DAO.
-    #[payable]
-    pub fn add_proposal(&mut self, proposal: ProposalInput) -> u64 {
-        // 0. validate bond attached.
-        // TODO: consider bond in the token of this DAO.
-        let policy = self.policy.get().unwrap().to_policy();
-
-        assert_eq!(
-            env::attached_deposit(),
-            policy.proposal_bond,
-            "ERR_MIN_BOND"
-        );
-
-        // 1. Validate proposal.
-        match &proposal.kind {
+    /// Validates given kind of proposal before adding it.
+    fn internal_validate_proposal_kind(&self, kind: &ProposalKind) {
+        match kind {
             ProposalKind::ChangePolicy { policy } => match policy {
-                VersionedPolicy::Current(_) => {}
+                VersionedPolicy::Current(policy) => {
+                    policy.assert_valid();
+                    self.internal_assert_quadratic_weight_available(
+                        policy
+                            .roles
+                            .iter()
+                            .flat_map(|role| role.vote_policy.values())
+                            .chain(std::iter::once(&policy.default_vote_policy)),
+                    );
+                }
                 _ => panic!("ERR_INVALID_POLICY"),
             },
+            ProposalKind::ChangePolicyAddOrUpdateRole { role } => {
+                self.internal_assert_quadratic_weight_available(role.vote_policy.values())
+            }
+            ProposalKind::ChangePolicyUpdateDefaultVotePolicy { vote_policy } => {
+                self.internal_assert_quadratic_weight_available(std::iter::once(vote_policy))
+            }
+            ProposalKind::AddMemberToRole { role, .. }
+            | ProposalKind::RemoveMemberFromRole { role, .. } => {
+                let policy = self.policy.get().unwrap().to_policy();
+                let role = policy.internal_get_role(role).expect("ERR_ROLE_NOT_FOUND");
+                assert!(
+                    matches!(role.kind, RoleKind::Group(_)),
+                    "ERR_ROLE_WRONG_KIND"
+                );
+            }
+            ProposalKind::ChangePolicyRemoveRole { role } => assert!(
+                self.policy
+                    .get()
+                    .unwrap()
+                    .to_policy()
+                    .internal_get_role(role)
+                    .is_some(),
+                "ERR_ROLE_NOT_FOUND"
+            ),
+            // Not validated: `UpgradeSelf` is executed by the factory with the code from the factory
+            // storage, and the DAO can't read it synchronously. Requiring the hash in `self.blobs`
+            // would reject upgrades to code that's only stored in the factory, so an unknown hash
+            // only fails when the proposal is executed.
+            ProposalKind::UpgradeRemote { hash, .. } => assert!(
+                self.blobs.contains_key(&CryptoHash::from(*hash)),
+                "ERR_NO_BLOB"
+            ),
+            ProposalKind::BountyDone { bounty_id, .. } => {
+                assert!(self.bounties.get(bounty_id).is_some(), "ERR_NO_BOUNTY")
+            }
             ProposalKind::Transfer { token_id, msg, .. } => {
                 assert!(
                     token_id != OLD_BASE_TOKEN || msg.is_none(),
@@ -559,15 +1558,127 @@ impl Contract {
                 self.staking_id.is_none(),
                 "ERR_STAKING_CONTRACT_CANT_CHANGE"
             ),
-            // TODO: add more verifications.
+            ProposalKind::FunctionCallChain { steps } => {
+                assert!(!steps.is_empty(), "ERR_EMPTY_CALL_CHAIN");
+            }
+            ProposalKind::RecurringPayment {
+                period, periods, ..
+            } => assert!(
+                period.0 > 0 && *periods > 0,
+                "ERR_INVALID_RECURRING_PAYMENT"
+            ),
+            ProposalKind::CancelRecurringPayment { schedule_id } => assert!(
+                self.recurring_payments.get(schedule_id).is_some(),
+                "ERR_NO_RECURRING_PAYMENT"
+            ),
+            ProposalKind::StakeToPool { amount, .. }
+            | ProposalKind::UnstakeFromPool { amount, .. }
+            | ProposalKind::WithdrawFromPool { amount, .. } => {
+                assert!(amount.0 > 0, "ERR_INVALID_AMOUNT")
+            }
+            ProposalKind::GrantAllowance { amount, period, .. } => {
+                assert!(amount.0 > 0 && period.0 > 0, "ERR_INVALID_ALLOWANCE")
+            }
+            ProposalKind::RevokeAllowance { allowance_id } => assert!(
+                self.allowances.get(allowance_id).is_some(),
+                "ERR_NO_ALLOWANCE"
+            ),
+            ProposalKind::BatchTransfer { payments, .. } => {
+                assert!(
+                    !payments.is_empty() && payments.iter().all(|(_, amount)| amount.0 > 0),
+                    "ERR_INVALID_BATCH_TRANSFER"
+                );
+                let mut receivers = HashSet::new();
+                for (receiver_id, _) in payments {
+                    assert!(receivers.insert(receiver_id), "ERR_DUPLICATE_RECEIVER");
+                }
+            }
+            ProposalKind::MtTransfer { tokens, .. } => assert!(
+                !tokens.is_empty() && tokens.iter().all(|(_, amount)| amount.0 > 0),
+                "ERR_INVALID_MT_TRANSFER"
+            ),
+            ProposalKind::CreateSubDao {
+                name,
+                policy,
+                parent_role,
+                ..
+            } => {
+                assert!(!name.as_str().contains('.'), "ERR_INVALID_SUB_DAO_NAME");
+                Self::internal_sub_dao_policy(policy, parent_role)
+                    .to_policy()
+                    .assert_valid();
+            }
+            ProposalKind::Batch { kinds } => {
+                assert!(!kinds.is_empty(), "ERR_EMPTY_BATCH");
+                for kind in kinds {
+                    // Bounty payouts are added through `bounty_done` and batches can't be nested.
+                    // Batch transfers track their payments by the promise results of their own.
+                    // Self upgrade returns the factory call as the result of the method, it can't be joined.
+                    assert!(
+                        !matches!(
+                            kind,
+                            ProposalKind::Batch { .. }
+                                | ProposalKind::BountyDone { .. }
+                                | ProposalKind::BatchTransfer { .. }
+                                | ProposalKind::UpgradeSelf { .. }
+                        ),
+                        "ERR_INVALID_BATCH_KIND"
+                    );
+                    self.internal_validate_proposal_kind(kind);
+                }
+            }
             _ => {}
         };
+    }
 
-        // 2. Check permission of caller to add this type of proposal.
+    /// Asserts that proposal can be changed by the caller: only proposer can do it before voting started.
+    fn internal_assert_proposal_editable(&self, proposal: &Proposal) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            proposal.proposer,
+            "ERR_INVALID_CALLER"
+        );
+        assert!(
+            matches!(proposal.status, ProposalStatus::InProgress),
+            "ERR_PROPOSAL_NOT_IN_PROGRESS"
+        );
+        assert!(proposal.votes.is_empty(), "ERR_PROPOSAL_HAS_VOTES");
+    }
+
+    pub(crate) fn internal_user_info(&self) -> UserInfo {
+        self.internal_user_info_of(env::predecessor_account_id())
+    }
+
+    pub(crate) fn internal_user_info_of(&self, account_id: AccountId) -> UserInfo {
+        UserInfo {
+            amount: self.get_user_weight(&account_id),
+            account_id,
+        }
+    }
+
+    /// Validates and adds proposal on behalf of the proposer, once the bond has been paid.
+    fn internal_add_proposal(
+        &mut self,
+        policy: &Policy,
+        proposer: AccountId,
+        proposal: ProposalInput,
+        token_bond: Option<TokenBond>,
+    ) -> u64 {
+        // 1. Validate proposal.
+        self.internal_validate_proposal_kind(&proposal.kind);
+        for dependency_id in &proposal.depends_on {
+            assert!(
+                self.proposals.contains_key(dependency_id)
+                    || self.pruned_proposals.contains_key(dependency_id),
+                "ERR_INVALID_DEPENDENCY"
+            );
+        }
+
+        // 2. Check permission of proposer to add this type of proposal.
         assert!(
             policy
                 .can_execute_action(
-                    self.internal_user_info(),
+                    self.internal_user_info_of(proposer.clone()),
                     &proposal.kind,
                     &Action::AddProposal
                 )
@@ -578,12 +1689,110 @@ impl Contract {
         // 3. Actually add proposal to the current list of proposals.
         let id = self.last_proposal_id;
         // 4. Log proposal creation
-        let mut proposal = VersionedProposal::from(proposal).into();
-        self.internal_log_action(id, Action::AddProposal, &mut proposal);
+        let mut proposal: Proposal = VersionedProposal::from(proposal).into();
+        proposal.proposer = proposer.clone();
+        proposal.token_bond = token_bond;
+        self.internal_log_action_by(proposer, id, Action::AddProposal, &mut proposal);
 
         self.proposals
             .insert(&id, &VersionedProposal::Latest(proposal));
         self.last_proposal_id += 1;
+        id
+    }
+
+    /// Adds proposal paid with the bond token of the policy, sent via `ft_on_transfer`.
+    pub(crate) fn internal_add_proposal_with_token_bond(
+        &mut self,
+        token_id: AccountId,
+        sender_id: AccountId,
+        amount: Balance,
+        proposal: ProposalInput,
+    ) -> u64 {
+        let policy = self.policy.get().unwrap().to_policy();
+        let token_bond = policy
+            .proposal_bond_token
+            .clone()
+            .expect("ERR_NO_BOND_TOKEN");
+        assert_eq!(token_bond.token_id, token_id, "ERR_WRONG_BOND_TOKEN");
+        assert_eq!(amount, token_bond.amount.0, "ERR_MIN_BOND");
+
+        let id = self.internal_add_proposal(&policy, sender_id, proposal, Some(token_bond));
+        let locked = self.locked_token_amounts.entry(token_id).or_default();
+        *locked = locked.saturating_add(amount);
+        id
+    }
+}
+
+#[near]
+impl FungibleTokenReceiver for Contract {
+    /// With empty `msg`, deposits the received tokens to the treasury. They are tracked in the
+    /// treasury ledger only if the token was registered with `register_treasury_token`.
+    /// Otherwise adds the proposal given in `msg`, using the received tokens as its bond.
+    /// Only the bond token of the policy is accepted for bonds, and the whole amount is used.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        if msg.is_empty() {
+            let token_id = env::predecessor_account_id();
+            if let Some(balance) = self.token_balances.get(&token_id) {
+                self.token_balances
+                    .insert(&token_id, &balance.saturating_add(amount.0));
+            }
+            return PromiseOrValue::Value(U128(0));
+        }
+        let proposal: ProposalInput =
+            near_sdk::serde_json::from_str(&msg).expect("ERR_INVALID_PROPOSAL_MSG");
+        self.internal_add_proposal_with_token_bond(
+            env::predecessor_account_id(),
+            sender_id,
+            amount.0,
+            proposal,
+        );
+        PromiseOrValue::Value(U128(0))
+    }
+}
+
+#[near]
+impl Contract {
+    /// Registers given fungible token in the treasury ledger, so its deposits are tracked.
+    /// Attached deposit pays for the storage of the ledger entry, the rest is refunded.
+    #[payable]
+    pub fn register_treasury_token(&mut self, token_id: AccountId) {
+        assert!(
+            self.token_balances.get(&token_id).is_none(),
+            "ERR_TOKEN_ALREADY_REGISTERED"
+        );
+        let initial_storage = env::storage_usage();
+        self.token_balances.insert(&token_id, &0);
+        let storage_cost = env::storage_byte_cost()
+            .saturating_mul(env::storage_usage().saturating_sub(initial_storage).into());
+        let refund = env::attached_deposit()
+            .checked_sub(storage_cost)
+            .expect("ERR_NOT_ENOUGH_DEPOSIT");
+        if !refund.is_zero() {
+            Promise::new(env::predecessor_account_id())
+                .transfer(refund)
+                .detach();
+        }
+    }
+
+    /// Add proposal to this DAO.
+    #[payable]
+    pub fn add_proposal(&mut self, proposal: ProposalInput) -> u64 {
+        // 0. validate bond attached.
+        // Bond in the token of this DAO can be paid via `ft_on_transfer`.
+        let policy = self.policy.get().unwrap().to_policy();
+
+        assert_eq!(
+            env::attached_deposit(),
+            policy.get_proposal_bond(proposal.kind.to_policy_label()),
+            "ERR_MIN_BOND"
+        );
+
+        let id = self.internal_add_proposal(&policy, env::predecessor_account_id(), proposal, None);
         self.locked_amount = self.locked_amount.saturating_add(env::attached_deposit());
         id
     }
@@ -609,7 +1818,11 @@ impl Contract {
         // Check permissions for the given action.
         let (roles, allowed) =
             policy.can_execute_action(self.internal_user_info(), &proposal.kind, &action);
-        assert!(allowed, "ERR_PERMISSION_DENIED");
+        // Queued proposals can be executed by anyone once the execution delay has passed.
+        assert!(
+            allowed || matches!(action, Action::Execute),
+            "ERR_PERMISSION_DENIED"
+        );
         let sender_id = env::predecessor_account_id();
 
         // Verify propolsal kind
@@ -635,11 +1848,14 @@ impl Contract {
                 );
 
                 // Updates proposal status with new votes using the policy.
-                proposal.status =
-                    policy.proposal_status(&proposal, roles, self.total_delegation_amount);
+                proposal.status = policy.proposal_status(
+                    &proposal,
+                    roles,
+                    self.total_delegation_amount,
+                    self.total_quadratic_delegation_amount,
+                );
                 if proposal.status == ProposalStatus::Approved {
-                    self.internal_execute_proposal(&policy, &proposal, id)
-                        .detach();
+                    self.internal_approve_proposal(&policy, &mut proposal, id);
                     true
                 } else if proposal.status == ProposalStatus::Removed {
                     self.internal_reject_proposal(&policy, &proposal, false)
@@ -655,25 +1871,42 @@ impl Contract {
                     true
                 }
             }
+            // Pending proposal is executed once all its dependencies are executed,
+            // or expires if any of them can't be executed anymore.
+            Action::Finalize if proposal.status == ProposalStatus::Pending => {
+                self.internal_approve_proposal(&policy, &mut proposal, id);
+                assert_ne!(
+                    proposal.status,
+                    ProposalStatus::Pending,
+                    "ERR_DEPENDENCIES_NOT_EXECUTED"
+                );
+                true
+            }
             // There are two cases when proposal must be finalized manually: expired or failed.
             // In case of failed, we just recompute the status and if it still approved, we re-execute the proposal.
             // In case of expired, we reject the proposal and return the bond.
+            // In case approval is no longer possible, the proposal is rejected early and the bond returned.
             // Corner cases:
             //  - if proposal expired during the failed state - it will be marked as expired.
+            //  - if failed proposal was retried as many times as the policy allows - it will be marked as expired.
             //  - if the number of votes in the group has changed (new members has been added) -
             //      the proposal can loose it's approved state. In this case new proposal needs to be made, this one can only expire.
             Action::Finalize => {
-                proposal.status = policy.proposal_status(
-                    &proposal,
-                    policy.roles.iter().map(|r| r.name.clone()).collect(),
-                    self.total_delegation_amount,
-                );
+                proposal.status = if !policy.can_retry(&proposal) {
+                    ProposalStatus::Expired
+                } else {
+                    policy.proposal_status(
+                        &proposal,
+                        policy.roles.iter().map(|r| r.name.clone()).collect(),
+                        self.total_delegation_amount,
+                        self.total_quadratic_delegation_amount,
+                    )
+                };
                 match proposal.status {
                     ProposalStatus::Approved => {
-                        self.internal_execute_proposal(&policy, &proposal, id)
-                            .detach();
+                        self.internal_approve_proposal(&policy, &mut proposal, id);
                     }
-                    ProposalStatus::Expired => {
+                    ProposalStatus::Expired | ProposalStatus::Rejected => {
                         self.internal_reject_proposal(&policy, &proposal, true)
                             .detach();
                     }
@@ -683,10 +1916,60 @@ impl Contract {
                 }
                 true
             }
-            Action::MoveToHub => false,
+            Action::MoveToHub => {
+                assert!(
+                    matches!(proposal.status, ProposalStatus::InProgress),
+                    "ERR_PROPOSAL_NOT_IN_PROGRESS"
+                );
+                self.internal_move_to_hub(&policy, &mut proposal, id)
+                    .detach();
+                true
+            }
+            Action::Execute => {
+                assert!(
+                    matches!(proposal.status, ProposalStatus::Queued),
+                    "ERR_PROPOSAL_NOT_QUEUED"
+                );
+                let queued_at = proposal.queued_at.expect("ERR_PROPOSAL_NOT_QUEUED").0;
+                assert!(
+                    env::block_timestamp()
+                        >= queued_at + policy.get_execution_delay(proposal.kind.to_policy_label()),
+                    "ERR_EXECUTION_DELAY_NOT_PASSED"
+                );
+                proposal.status = ProposalStatus::Approved;
+                self.internal_execute_proposal(&policy, &mut proposal, id)
+                    .detach();
+                true
+            }
+            Action::Cancel => {
+                assert!(
+                    matches!(proposal.status, ProposalStatus::Queued),
+                    "ERR_PROPOSAL_NOT_QUEUED"
+                );
+                proposal.status = ProposalStatus::Rejected;
+                self.internal_reject_proposal(&policy, &proposal, true)
+                    .detach();
+                true
+            }
+            Action::Veto => {
+                assert!(
+                    matches!(
+                        proposal.status,
+                        ProposalStatus::InProgress
+                            | ProposalStatus::Queued
+                            | ProposalStatus::Pending
+                    ),
+                    "ERR_PROPOSAL_NOT_READY_FOR_VETO"
+                );
+                proposal.status = ProposalStatus::Vetoed;
+                self.internal_reject_proposal(&policy, &proposal, true)
+                    .detach();
+                true
+            }
         };
 
         if update {
+            proposal.update_finalized_at();
             self.proposals
                 .insert(&id, &VersionedProposal::Latest(proposal));
         }
@@ -695,7 +1978,197 @@ impl Contract {
         }
     }
 
+    /// Replaces given finalized proposals with their summaries to reclaim the storage.
+    /// Only executed, rejected, vetoed, expired or moved proposals finalized earlier than
+    /// the retention period of the policy can be pruned. Approved proposals waiting for
+    /// their execution to be confirmed are not finalized yet. Can be called by anyone.
+    pub fn prune_proposals(&mut self, ids: Vec<u64>) {
+        let policy = self.policy.get().unwrap().to_policy();
+        let retention_period = policy
+            .proposal_retention_period
+            .expect("ERR_PRUNING_DISABLED")
+            .0;
+        for id in ids {
+            let versioned_proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL");
+            let hash = env::sha256_array(near_sdk::borsh::to_vec(&versioned_proposal).unwrap());
+            let proposal: Proposal = versioned_proposal.into();
+            assert!(
+                proposal.finalization_time().is_some_and(|time| time
+                    .saturating_add(retention_period)
+                    < env::block_timestamp()),
+                "ERR_PROPOSAL_NOT_PRUNABLE"
+            );
+            self.proposals.remove(&id);
+            self.pruned_proposals.insert(
+                &id,
+                &ProposalSummary {
+                    proposer: proposal.proposer,
+                    kind: proposal.kind.to_policy_label().to_string(),
+                    status: proposal.status,
+                    vote_counts: proposal.vote_counts,
+                    moved_to: proposal.moved_to,
+                    failure: proposal.failure,
+                    hash: hash.into(),
+                },
+            );
+        }
+    }
+
+    /// Withdraw given proposal by its proposer before anyone voted on it.
+    /// Removes the proposal and returns the bond.
+    pub fn withdraw_proposal(&mut self, id: u64) {
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        self.internal_assert_proposal_editable(&proposal);
+        self.internal_log_action(id, LoggedAction::WithdrawProposal, &mut proposal);
+
+        let policy = self.policy.get().unwrap().to_policy();
+        self.internal_reject_proposal(&policy, &proposal, true)
+            .detach();
+        self.proposals.remove(&id);
+    }
+
+    /// Amend description and kind of given proposal by its proposer before anyone voted on it.
+    /// Kind of `BountyDone` proposals can't be changed, as they are created from the bounty claims.
+    pub fn amend_proposal(&mut self, id: u64, description: String, kind: ProposalKind) {
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        self.internal_assert_proposal_editable(&proposal);
+        if proposal.kind != kind {
+            assert!(
+                !matches!(proposal.kind, ProposalKind::BountyDone { .. })
+                    && !matches!(kind, ProposalKind::BountyDone { .. }),
+                "ERR_WRONG_KIND"
+            );
+            self.internal_validate_proposal_kind(&kind);
+            let policy = self.policy.get().unwrap().to_policy();
+            // Bond was attached for the original kind, so it must stay the same.
+            assert!(
+                proposal.token_bond.is_some()
+                    || policy.get_proposal_bond(kind.to_policy_label())
+                        == policy.get_proposal_bond(proposal.kind.to_policy_label()),
+                "ERR_MIN_BOND"
+            );
+            assert!(
+                policy
+                    .can_execute_action(self.internal_user_info(), &kind, &Action::AddProposal)
+                    .1,
+                "ERR_PERMISSION_DENIED"
+            );
+        }
+        self.internal_log_action(id, LoggedAction::AmendProposal, &mut proposal);
+
+        proposal.description = description;
+        proposal.kind = kind;
+        self.proposals
+            .insert(&id, &VersionedProposal::Latest(proposal));
+    }
+
+    /// Callback after a step of the function call chain.
+    /// Fails if the previous step failed, which fails the whole proposal.
+    /// Otherwise executes the next step.
+    #[private]
+    pub fn on_call_chain_step_callback(&mut self, steps: Vec<FunctionCallStep>) -> Promise {
+        assert!(utils::is_promise_success(), "ERR_CALL_CHAIN_STEP_FAILED");
+        Self::internal_function_call_chain(&steps)
+    }
+
+    /// Callback after reading the policy of the hub.
+    /// Bond in the hub is paid by this DAO, as it becomes the proposer there and gets it back.
+    /// Bond of the proposer stays locked until the proposal has been added to the hub.
+    /// If the policy can't be read or the bond isn't available, the proposal is back in progress.
+    #[private]
+    pub fn on_hub_policy_callback(
+        &mut self,
+        proposal_id: u64,
+        hub_id: AccountId,
+        #[callback_result] hub_policy: Result<Policy, PromiseError>,
+    ) -> PromiseOrValue<()> {
+        let mut proposal: Proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect("ERR_NO_PROPOSAL")
+            .into();
+        let bond = hub_policy
+            .ok()
+            .map(|hub_policy| hub_policy.get_proposal_bond(proposal.kind.to_policy_label()))
+            .filter(|bond| self.get_available_amount() >= *bond);
+        match bond {
+            Some(bond) => self
+                .internal_add_to_hub(&proposal, proposal_id, hub_id, bond)
+                .into(),
+            None => {
+                log!("Proposal {} can't be moved to {}", proposal_id, hub_id);
+                proposal.status = ProposalStatus::InProgress;
+                self.proposals
+                    .insert(&proposal_id, &VersionedProposal::Latest(proposal));
+                PromiseOrValue::Value(())
+            }
+        }
+    }
+
+    /// Callback after the proposal has been added to the hub.
+    /// Records id of the proposal in the hub and returns the bond to the proposer.
+    /// If adding failed, the proposal is back in progress and the bond stays locked.
+    #[private]
+    pub fn on_move_to_hub_callback(
+        &mut self,
+        proposal_id: u64,
+        hub_id: AccountId,
+        #[callback_result] result: Result<u64, PromiseError>,
+    ) {
+        let mut proposal: Proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect("ERR_NO_PROPOSAL")
+            .into();
+        match result {
+            Ok(hub_proposal_id) => {
+                proposal.moved_to = Some(RemoteProposal {
+                    dao_id: hub_id,
+                    proposal_id: hub_proposal_id,
+                });
+                let policy = self.policy.get().unwrap().to_policy().clone();
+                self.internal_return_bonds(&policy, &proposal).detach();
+            }
+            Err(_) => {
+                proposal.status = ProposalStatus::InProgress;
+            }
+        }
+        proposal.update_finalized_at();
+        self.proposals
+            .insert(&proposal_id, &VersionedProposal::Latest(proposal));
+    }
+
+    /// Callback after checking the storage of the fungible token payout receiver.
+    /// Registers the receiver with up to `ft_storage_deposit` of the policy if it has no storage yet,
+    /// then transfers the tokens.
+    /// Result of the transfer is passed on to the callback of the payout.
+    #[private]
+    pub fn on_ft_storage_balance_callback(
+        &mut self,
+        token_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: String,
+        msg: Option<String>,
+        #[callback_result] storage_balance: Result<Option<StorageBalance>, PromiseError>,
+    ) -> Promise {
+        let transfer = || Self::internal_ft_transfer(&token_id, &receiver_id, amount.0, memo, msg);
+        // Token may not implement storage management, then the transfer is attempted anyway.
+        let max_deposit = self.policy.get().unwrap().to_policy().ft_storage_deposit;
+        if let (Ok(None), Some(max_deposit)) = (storage_balance, max_deposit) {
+            // Registration only, the deposit above the minimum is refunded by the token.
+            ext_fungible_token::ext(token_id.clone())
+                .with_attached_deposit(max_deposit)
+                .with_static_gas(GAS_FOR_STORAGE_DEPOSIT)
+                .storage_deposit(Some(receiver_id.clone()), Some(true))
+                .then(transfer())
+        } else {
+            transfer()
+        }
+    }
+
     /// Receiving callback after the proposal has been finalized.
+    /// Expects one or more promise results, e.g. from the batch proposal.
     /// If successful, returns bond money to the proposal originator.
     /// If the proposal execution failed (funds didn't transfer or function call failure),
     /// move proposal to "Failed" state.
@@ -706,16 +2179,26 @@ impl Contract {
             .get(&proposal_id)
             .expect("ERR_NO_PROPOSAL")
             .into();
-        assert_eq!(
-            env::promise_results_count(),
-            1,
+        assert!(
+            env::promise_results_count() > 0,
             "ERR_UNEXPECTED_CALLBACK_PROMISES"
         );
-        let result = if utils::is_promise_success() {
+        // Batch proposals join promises of all inner kinds, all of them must succeed.
+        let failed_promises: Vec<u64> = (0..env::promise_results_count())
+            .filter(|i| {
+                matches!(
+                    env::promise_result_checked(*i, 0),
+                    Err(PromiseError::Failed)
+                )
+            })
+            .collect();
+        self.internal_record_completed(&mut proposal, &failed_promises);
+        let result = if failed_promises.is_empty() {
             self.internal_callback_proposal_success(&mut proposal)
         } else {
-            self.internal_callback_proposal_fail(&mut proposal)
+            self.internal_callback_proposal_fail(&mut proposal, failed_promises)
         };
+        proposal.update_finalized_at();
         self.proposals
             .insert(&proposal_id, &VersionedProposal::Latest(proposal));
         result
@@ -0,0 +1,275 @@
+use near_sdk::json_types::{U64, U128};
+use near_sdk::{AccountId, PromiseOrValue, env, utils};
+
+use crate::types::{GAS_FOR_FT_TRANSFER, OldAccountId, convert_old_to_new_token};
+use crate::*;
+
+/// Schedule of payments created by an approved `RecurringPayment` proposal.
+#[near(serializers=[borsh, json])]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(deny_unknown_fields)]
+pub struct RecurringPayment {
+    /// Token the payments are paid out in.
+    /// Can be "" for $NEAR or a valid account id.
+    pub token_id: OldAccountId,
+    /// Receiver of the payments.
+    pub receiver_id: AccountId,
+    /// Amount paid out per period.
+    pub amount: U128,
+    /// Length of a period in nanoseconds.
+    pub period: U64,
+    /// Total number of periods.
+    pub periods: u32,
+    /// Start of the first period, i.e. when the proposal got executed.
+    pub start_time: U64,
+    /// Number of periods already paid out.
+    pub claimed_periods: u32,
+}
+
+impl RecurringPayment {
+    /// Number of periods that have passed but are not paid out yet.
+    fn accrued_periods(&self) -> u32 {
+        let elapsed = env::block_timestamp().saturating_sub(self.start_time.0) / self.period.0;
+        let passed = std::cmp::min(elapsed, self.periods as u64) as u32;
+        passed.saturating_sub(self.claimed_periods)
+    }
+}
+
+impl Contract {
+    /// Adds recurring payment to the storage and returns it's id.
+    pub(crate) fn internal_add_recurring_payment(
+        &mut self,
+        token_id: &OldAccountId,
+        receiver_id: &AccountId,
+        amount: U128,
+        period: U64,
+        periods: u32,
+    ) -> u64 {
+        let id = self.last_recurring_payment_id;
+        self.recurring_payments.insert(
+            &id,
+            &RecurringPayment {
+                token_id: token_id.clone(),
+                receiver_id: receiver_id.clone(),
+                amount,
+                period,
+                periods,
+                start_time: U64::from(env::block_timestamp()),
+                claimed_periods: 0,
+            },
+        );
+        self.last_recurring_payment_id += 1;
+        id
+    }
+}
+
+#[near]
+impl Contract {
+    /// Pays out the amount accrued since the last claim to the receiver of the recurring payment.
+    /// Can be called by anyone. Fails if nothing has accrued yet.
+    pub fn claim_recurring_payment(&mut self, schedule_id: u64) -> PromiseOrValue<()> {
+        let mut payment = self
+            .recurring_payments
+            .get(&schedule_id)
+            .expect("ERR_NO_RECURRING_PAYMENT");
+        let periods = payment.accrued_periods();
+        assert!(periods > 0, "ERR_NOTHING_TO_CLAIM");
+        payment.claimed_periods += periods;
+        self.recurring_payments.insert(&schedule_id, &payment);
+
+        match self.internal_payout(
+            &convert_old_to_new_token(&payment.token_id),
+            &payment.receiver_id,
+            payment.amount.0 * periods as u128,
+            format!("Recurring payment {} payout", schedule_id),
+            None,
+        ) {
+            PromiseOrValue::Promise(promise) => promise
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_FT_TRANSFER)
+                        .on_recurring_payment_callback(
+                            schedule_id,
+                            payment.token_id.clone(),
+                            payment.amount,
+                            periods,
+                        ),
+                )
+                .into(),
+            value => value,
+        }
+    }
+
+    /// Callback after the recurring payment payout.
+    /// Schedule is removed once all its periods are paid out.
+    /// If the payout failed, the claimed periods can be claimed again.
+    #[private]
+    pub fn on_recurring_payment_callback(
+        &mut self,
+        schedule_id: u64,
+        token_id: OldAccountId,
+        amount: U128,
+        periods: u32,
+    ) {
+        if utils::is_promise_success() {
+            self.internal_debit_token(&token_id, amount.0 * periods as u128);
+            if self
+                .recurring_payments
+                .get(&schedule_id)
+                .is_some_and(|payment| payment.claimed_periods == payment.periods)
+            {
+                self.recurring_payments.remove(&schedule_id);
+            }
+            return;
+        }
+        // Schedule may have been cancelled in the meantime.
+        if let Some(mut payment) = self.recurring_payments.get(&schedule_id) {
+            payment.claimed_periods -= periods;
+            self.recurring_payments.insert(&schedule_id, &payment);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_api::types::NearToken;
+    use near_sdk::test_utils::{VMContextBuilder, accounts};
+    use near_sdk::{PromiseResult, testing_env};
+
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::{Action, Config};
+
+    use super::*;
+    use crate::tests::add_approved_proposal;
+
+    const PERIOD: u64 = 1_000_000_000 * 60 * 60 * 24 * 30;
+
+    fn add_recurring_payment(context: &mut VMContextBuilder, contract: &mut Contract) -> u64 {
+        add_approved_proposal(
+            context,
+            contract,
+            ProposalKind::RecurringPayment {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(NearToken::from_near(10).as_yoctonear()),
+                period: U64(PERIOD),
+                periods: 3,
+            },
+        );
+        contract.get_last_recurring_payment_id() - 1
+    }
+
+    #[test]
+    fn test_recurring_payment_claims() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_recurring_payment(&mut context, &mut contract);
+
+        testing_env!(context.block_timestamp(PERIOD * 2 + 1).build());
+        assert!(matches!(
+            contract.claim_recurring_payment(id),
+            PromiseOrValue::Promise(_)
+        ));
+        assert_eq!(
+            contract
+                .get_recurring_payment(id)
+                .recurring_payment
+                .claimed_periods,
+            2
+        );
+
+        // Failed payout can be claimed again.
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_recurring_payment_callback(
+            id,
+            String::from(OLD_BASE_TOKEN),
+            U128(NearToken::from_near(10).as_yoctonear()),
+            2,
+        );
+        assert_eq!(
+            contract
+                .get_recurring_payment(id)
+                .recurring_payment
+                .claimed_periods,
+            0
+        );
+
+        // Accrues no more than the number of periods.
+        testing_env!(context.block_timestamp(PERIOD * 10).build());
+        let _ = contract.claim_recurring_payment(id);
+        assert_eq!(
+            contract
+                .get_recurring_payment(id)
+                .recurring_payment
+                .claimed_periods,
+            3
+        );
+
+        // Schedule is removed once all its periods are paid out.
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_recurring_payment_callback(
+            id,
+            String::from(OLD_BASE_TOKEN),
+            U128(NearToken::from_near(10).as_yoctonear()),
+            3,
+        );
+        assert!(contract.recurring_payments.get(&id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOTHING_TO_CLAIM")]
+    fn test_recurring_payment_nothing_to_claim() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_recurring_payment(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(PERIOD - 1).build());
+        let _ = contract.claim_recurring_payment(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_RECURRING_PAYMENT")]
+    fn test_cancel_recurring_payment() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_recurring_payment(&mut context, &mut contract);
+
+        let proposal_id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::CancelRecurringPayment { schedule_id: id },
+            depends_on: vec![],
+        });
+        contract.act_proposal(
+            proposal_id,
+            Action::VoteApprove,
+            contract.get_proposal(proposal_id).proposal.kind,
+            None,
+        );
+        testing_env!(context.block_timestamp(PERIOD).build());
+        let _ = contract.claim_recurring_payment(id);
+    }
+}
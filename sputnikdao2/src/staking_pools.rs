@@ -0,0 +1,186 @@
+use near_sdk::json_types::U128;
+use near_sdk::{Promise, ext_contract};
+
+use crate::*;
+
+/// Interface of the validator staking pool.
+#[allow(dead_code)]
+#[ext_contract(ext_staking_pool)]
+pub trait StakingPool {
+    fn deposit_and_stake(&mut self);
+    fn unstake(&mut self, amount: U128);
+    fn withdraw(&mut self, amount: U128);
+}
+
+/// Amount of $NEAR the DAO believes it has in the staking pool.
+/// Rewards are not tracked, so the pool may hold more than this.
+#[near(serializers=[borsh, json])]
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct PoolBalance {
+    /// Amount staked with `StakeToPool` proposals.
+    pub staked: U128,
+    /// Amount unstaked with `UnstakeFromPool` proposals, but not withdrawn yet.
+    pub unstaked: U128,
+}
+
+impl Contract {
+    /// Executes given staking pool proposal kind.
+    pub(crate) fn internal_execute_pool_action(&self, kind: &ProposalKind) -> Promise {
+        match kind {
+            ProposalKind::StakeToPool { pool_id, amount } => ext_staking_pool::ext(pool_id.clone())
+                .with_attached_deposit(NearToken::from_yoctonear(amount.0))
+                .deposit_and_stake(),
+            ProposalKind::UnstakeFromPool { pool_id, amount } => {
+                ext_staking_pool::ext(pool_id.clone()).unstake(*amount)
+            }
+            ProposalKind::WithdrawFromPool { pool_id, amount } => {
+                ext_staking_pool::ext(pool_id.clone()).withdraw(*amount)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Updates tracked pool balances once given proposal kind has been executed successfully.
+    pub(crate) fn internal_update_pool_balances(&mut self, kind: &ProposalKind) {
+        let (pool_id, balance) = match kind {
+            ProposalKind::StakeToPool { pool_id, amount } => {
+                let mut balance = self.pool_balances.get(pool_id).unwrap_or_default();
+                balance.staked = U128(balance.staked.0 + amount.0);
+                (pool_id, balance)
+            }
+            ProposalKind::UnstakeFromPool { pool_id, amount } => {
+                let mut balance = self.pool_balances.get(pool_id).unwrap_or_default();
+                balance.staked = U128(balance.staked.0.saturating_sub(amount.0));
+                balance.unstaked = U128(balance.unstaked.0 + amount.0);
+                (pool_id, balance)
+            }
+            ProposalKind::WithdrawFromPool { pool_id, amount } => {
+                let mut balance = self.pool_balances.get(pool_id).unwrap_or_default();
+                balance.unstaked = U128(balance.unstaked.0.saturating_sub(amount.0));
+                (pool_id, balance)
+            }
+            _ => return,
+        };
+        if balance == PoolBalance::default() {
+            self.pool_balances.remove(pool_id);
+        } else {
+            self.pool_balances.insert(pool_id, &balance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_api::types::NearToken;
+    use near_sdk::test_utils::{VMContextBuilder, accounts};
+    use near_sdk::{PromiseResult, testing_env};
+
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::{Action, Config};
+
+    use super::*;
+    use crate::tests::add_approved_proposal;
+
+    fn execute(context: &mut VMContextBuilder, contract: &mut Contract, kind: ProposalKind) {
+        let id = add_approved_proposal(context, contract, kind);
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let _ = contract.on_proposal_callback(id);
+    }
+
+    #[test]
+    fn test_pool_balances() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let pool_id = accounts(3);
+        let amount = |near| U128(NearToken::from_near(near).as_yoctonear());
+        execute(
+            &mut context,
+            &mut contract,
+            ProposalKind::StakeToPool {
+                pool_id: pool_id.clone(),
+                amount: amount(10),
+            },
+        );
+        execute(
+            &mut context,
+            &mut contract,
+            ProposalKind::UnstakeFromPool {
+                pool_id: pool_id.clone(),
+                amount: amount(4),
+            },
+        );
+        let treasury = contract.get_treasury(0, 10);
+        assert_eq!(treasury.staked_amount, NearToken::from_near(6));
+        assert_eq!(treasury.unstaked_amount, NearToken::from_near(4));
+        assert_eq!(
+            treasury.pools[&pool_id],
+            PoolBalance {
+                staked: amount(6),
+                unstaked: amount(4),
+            }
+        );
+
+        execute(
+            &mut context,
+            &mut contract,
+            ProposalKind::UnstakeFromPool {
+                pool_id: pool_id.clone(),
+                amount: amount(6),
+            },
+        );
+        execute(
+            &mut context,
+            &mut contract,
+            ProposalKind::WithdrawFromPool {
+                pool_id,
+                amount: amount(10),
+            },
+        );
+        assert!(contract.get_treasury(0, 10).pools.is_empty());
+    }
+
+    #[test]
+    fn test_failed_stake_is_not_tracked() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(NearToken::from_near(1)).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::StakeToPool {
+                pool_id: accounts(3),
+                amount: U128(NearToken::from_near(10).as_yoctonear()),
+            },
+            depends_on: vec![],
+        });
+        contract.act_proposal(
+            id,
+            Action::VoteApprove,
+            contract.get_proposal(id).proposal.kind,
+            None,
+        );
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let _ = contract.on_proposal_callback(id);
+        assert!(contract.get_treasury(0, 10).pools.is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::serde_json::json;
+use near_sdk::{Promise, ext_contract};
+
+use crate::types::GAS_FOR_CREATE_SUB_DAO;
+use crate::upgrade::internal_get_factory_info;
+use crate::*;
+
+/// Interface of the factory that creates DAOs.
+#[allow(dead_code)]
+#[ext_contract(ext_factory)]
+pub trait SputnikDAOFactory {
+    fn create(&mut self, name: AccountId, args: Base64VecU8);
+}
+
+impl Contract {
+    /// Returns policy of the sub-DAO, with this DAO added to the `parent_role` if given.
+    pub(crate) fn internal_sub_dao_policy(
+        policy: &VersionedPolicy,
+        parent_role: &Option<String>,
+    ) -> VersionedPolicy {
+        let mut policy = policy.clone().upgrade();
+        if let Some(role) = parent_role {
+            let role = policy
+                .to_policy_mut()
+                .roles
+                .iter_mut()
+                .find(|r| &r.name == role)
+                .expect("ERR_ROLE_NOT_FOUND");
+            role.kind
+                .add_member_to_group(&env::current_account_id())
+                .unwrap_or_else(|()| env::panic_str("ERR_ROLE_WRONG_KIND"));
+        }
+        policy
+    }
+
+    /// Creates sub-DAO with given name through the factory of this DAO.
+    pub(crate) fn internal_create_sub_dao(
+        &self,
+        name: &AccountId,
+        config: &Config,
+        policy: &VersionedPolicy,
+        deposit: U128,
+        parent_role: &Option<String>,
+    ) -> Promise {
+        let args = json!({
+            "config": config,
+            "policy": Self::internal_sub_dao_policy(policy, parent_role),
+        });
+        ext_factory::ext(internal_get_factory_info().factory_id)
+            .with_attached_deposit(NearToken::from_yoctonear(deposit.0))
+            .with_static_gas(GAS_FOR_CREATE_SUB_DAO)
+            .create(name.clone(), Base64VecU8(args.to_string().into_bytes()))
+    }
+
+    /// Returns account of the sub-DAO with given name created by the factory of this DAO.
+    pub(crate) fn internal_sub_dao_id(name: &AccountId) -> AccountId {
+        format!("{}.{}", name, internal_get_factory_info().factory_id)
+            .parse()
+            .expect("ERR_INVALID_SUB_DAO_NAME")
+    }
+}
+
+#[near]
+impl Contract {
+    /// Returns number of sub-DAOs created by this DAO.
+    pub fn get_number_sub_daos(&self) -> u64 {
+        self.sub_daos.len()
+    }
+
+    /// Returns sub-DAOs created by this DAO in paginated view.
+    pub fn get_sub_daos(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        let elements = self.sub_daos.as_vector();
+        (from_index..std::cmp::min(from_index + limit, elements.len()))
+            .filter_map(|index| elements.get(index))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_api::types::NearToken;
+    use near_sdk::test_utils::{VMContextBuilder, accounts};
+    use near_sdk::{PromiseResult, testing_env};
+
+    use crate::Config;
+    use crate::proposals::ProposalKind;
+
+    use super::*;
+    use crate::tests::add_approved_proposal;
+
+    fn add_sub_dao_proposal(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        parent_role: Option<String>,
+    ) -> u64 {
+        add_approved_proposal(
+            context,
+            contract,
+            ProposalKind::CreateSubDao {
+                name: "group".parse().unwrap(),
+                config: Config::test_config(),
+                policy: VersionedPolicy::Default(vec![accounts(2)]),
+                deposit: U128(NearToken::from_near(5).as_yoctonear()),
+                parent_role,
+            },
+        )
+    }
+
+    #[test]
+    fn test_create_sub_dao_failed() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id("dao.sputnik-dao.near".parse().unwrap());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_sub_dao_proposal(&mut context, &mut contract, None);
+        testing_env!(
+            context.build(),
+            near_sdk::test_vm_config(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let _ = contract.on_proposal_callback(id);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert_eq!(contract.get_number_sub_daos(), 0);
+    }
+
+    #[test]
+    fn test_sub_dao_policy_with_parent() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id("dao.sputnik-dao.near".parse().unwrap());
+        testing_env!(context.build());
+        let policy = Contract::internal_sub_dao_policy(
+            &VersionedPolicy::Default(vec![accounts(2)]),
+            &Some("council".to_string()),
+        )
+        .to_policy();
+        assert_eq!(
+            policy.roles[1].kind,
+            RoleKind::Group(
+                [accounts(2), "dao.sputnik-dao.near".parse().unwrap()]
+                    .into_iter()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ROLE_WRONG_KIND")]
+    fn test_sub_dao_parent_role_wrong_kind() {
+        let mut context = VMContextBuilder::new();
+        context.current_account_id("dao.sputnik-dao.near".parse().unwrap());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        add_sub_dao_proposal(&mut context, &mut contract, Some("all".to_string()));
+    }
+}
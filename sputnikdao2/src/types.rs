@@ -15,6 +15,49 @@ pub const ONE_YOCTO_NEAR: NearToken = NearToken::from_yoctonear(1);
 /// Gas for single ft_transfer call.
 pub const GAS_FOR_FT_TRANSFER: Gas = Gas::from_tgas(10);
 
+/// Gas for storage_balance_of call, checking if the payout receiver is registered with the token.
+pub const GAS_FOR_STORAGE_BALANCE_OF: Gas = Gas::from_tgas(5);
+
+/// Gas for storage_deposit call, registering the payout receiver with the token.
+pub const GAS_FOR_STORAGE_DEPOSIT: Gas = Gas::from_tgas(10);
+
+/// Gas for the callback registering the payout receiver if needed and scheduling the transfer.
+pub const GAS_FOR_FT_STORAGE_CALLBACK: Gas = Gas::from_tgas(30);
+
+/// Gas for the callback scheduling next step of the function call chain.
+pub const GAS_FOR_CALL_CHAIN_STEP: Gas = Gas::from_tgas(10);
+
+/// Gas for adding the moved proposal to the hub DAO.
+pub const GAS_FOR_MOVE_TO_HUB: Gas = Gas::from_tgas(30);
+
+/// Gas for reading the policy of the hub DAO.
+pub const GAS_FOR_GET_POLICY: Gas = Gas::from_tgas(10);
+
+/// Gas for the callback forwarding the moved proposal with the bond read from the hub policy,
+/// including adding it to the hub and the callback after that.
+pub const GAS_FOR_HUB_POLICY_CALLBACK: Gas = Gas::from_tgas(60);
+
+/// Gas for single nft_transfer call.
+pub const GAS_FOR_NFT_TRANSFER: Gas = Gas::from_tgas(10);
+
+/// Gas for single nft_transfer_call call, including the receiver call and resolving the transfer.
+pub const GAS_FOR_NFT_TRANSFER_CALL: Gas = Gas::from_tgas(50);
+
+/// Gas for single mt_batch_transfer call.
+pub const GAS_FOR_MT_TRANSFER: Gas = Gas::from_tgas(20);
+
+/// Gas for single mt_batch_transfer_call call, including the receiver call and resolving the transfer.
+pub const GAS_FOR_MT_TRANSFER_CALL: Gas = Gas::from_tgas(50);
+
+/// Gas for creating the sub-DAO through the factory, including the `new` call and the factory callback.
+pub const GAS_FOR_CREATE_SUB_DAO: Gas = Gas::from_tgas(80);
+
+/// Maximum length of the JSON boolean returned by a promise, e.g. by `nft_transfer_call`.
+pub const MAX_BOOL_RESULT_LEN: usize = 5;
+
+/// Maximum length of the JSON `U128` string returned by a promise, e.g. by `ft_transfer_call`.
+pub const MAX_U128_RESULT_LEN: usize = 41;
+
 /// Configuration of the DAO.
 #[derive(Clone, Debug, PartialEq)]
 #[near(serializers=[borsh, json])]
@@ -60,9 +103,33 @@ pub enum Action {
     Finalize,
     /// Move a proposal to the hub to shift into another DAO.
     MoveToHub,
+    /// Execute queued proposal after its execution delay has passed.
+    /// Allowed for everyone, even without `<proposal_kind>:Execute` permission in the policy:
+    /// the proposal was already approved, so this only triggers the execution once it's due.
+    Execute,
+    /// Cancel queued proposal before it gets executed and return the bond.
+    /// Used by the guardian role, configured via `<proposal_kind>:Cancel` permissions.
+    Cancel,
+    /// Veto given proposal that is in progress or queued. Bond is returned.
+    /// Allows a minority role to block proposals without being able to pass them.
+    Veto,
 }
 
 impl Action {
+    /// Labels of all the actions, as used in the policy permissions.
+    pub const LABELS: &[&str] = &[
+        "AddProposal",
+        "RemoveProposal",
+        "VoteApprove",
+        "VoteReject",
+        "VoteRemove",
+        "Finalize",
+        "MoveToHub",
+        "Execute",
+        "Cancel",
+        "Veto",
+    ];
+
     pub fn to_policy_label(&self) -> String {
         format!("{:?}", self)
     }
@@ -48,6 +48,26 @@ pub struct ContractV1 {
     pub blobs: LookupMap<CryptoHash, AccountId>,
 }
 
+/// Contract state before bonds in fungible tokens were introduced.
+#[near]
+#[derive(PanicOnDefault)]
+pub struct ContractV2 {
+    pub config: LazyOption<Config>,
+    pub policy: LazyOption<VersionedPolicy>,
+    pub locked_amount: NearToken,
+    pub staking_id: Option<AccountId>,
+    pub total_delegation_amount: Balance,
+    pub delegations: LookupMap<AccountId, Balance>,
+    pub last_proposal_id: u64,
+    pub proposals: LookupMap<u64, VersionedProposal>,
+    pub last_bounty_id: u64,
+    pub bounties: LookupMap<u64, VersionedBounty>,
+    pub bounty_claimers: LookupMap<AccountId, Vec<BountyClaim>>,
+    pub bounty_claims_count: LookupMap<u64, u32>,
+    pub blobs: LookupMap<CryptoHash, AccountId>,
+    pub actions_log: VecDeque<ActionLog>,
+}
+
 /// Info about factory that deployed this contract and if auto-update is allowed.
 #[derive(PartialEq, Clone)]
 #[near(serializers=[borsh, json])]
@@ -63,6 +83,7 @@ pub struct FactoryInfo {
 pub(crate) enum StateVersion {
     V1,
     V2,
+    V3,
 }
 
 const VERSION_KEY: &[u8] = b"STATEVERSION";
@@ -191,6 +212,9 @@ pub fn update() {
                     // Proposal is old enough — mark it as Approved and proceed
                     // with the deployment below.
                     proposal.status = ProposalStatus::Approved;
+                    // Deployment happens in this call, it fails together with it.
+                    proposal.executed = true;
+                    proposal.update_finalized_at();
                     contract
                         .proposals
                         .insert(&id, &VersionedProposal::Latest(proposal));
@@ -214,6 +238,15 @@ pub fn update() {
                     votes: Default::default(),
                     submission_time: U64::from(env::block_timestamp()),
                     last_actions_log: Default::default(),
+                    queued_at: None,
+                    token_bond: None,
+                    depends_on: vec![],
+                    moved_to: None,
+                    failure: None,
+                    completed_payments: vec![],
+                    completed_kinds: vec![],
+                    executed: false,
+                    finalized_at: None,
                 };
                 let id = contract.last_proposal_id;
                 contract
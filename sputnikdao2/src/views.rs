@@ -1,5 +1,6 @@
 use std::cmp::min;
 
+use crate::proposals::Vote;
 use crate::*;
 
 /// This is format of output via JSON for the proposal.
@@ -12,6 +13,26 @@ pub struct ProposalOutput {
     pub proposal: Proposal,
 }
 
+/// This is format of output via JSON for the recurring payment.
+#[near(serializers=[borsh, json])]
+pub struct RecurringPaymentOutput {
+    /// Id of the recurring payment.
+    pub id: u64,
+    #[serde(flatten)]
+    pub recurring_payment: RecurringPayment,
+}
+
+/// This is format of output via JSON for the allowance.
+#[near(serializers=[json])]
+pub struct AllowanceOutput {
+    /// Id of the allowance.
+    pub id: u64,
+    #[serde(flatten)]
+    pub allowance: Allowance,
+    /// Amount that can still be spent in the current period.
+    pub remaining: U128,
+}
+
 /// This is format of output via JSON for the bounty.
 #[near(serializers=[borsh, json])]
 pub struct BountyOutput {
@@ -21,6 +42,48 @@ pub struct BountyOutput {
     pub bounty: Bounty,
 }
 
+/// Votes of the role on the proposal, as returned by `simulate_action`.
+#[near(serializers=[json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct RoleTally {
+    /// Name of the role.
+    pub role: String,
+    /// Weight of approve, reject and remove votes.
+    pub vote_counts: [U128; 3],
+    /// Total weight of the role.
+    pub total_weight: U128,
+    /// Weight of votes needed to approve, reject or remove the proposal.
+    pub threshold: U128,
+    /// Weight of approve votes still missing to reach the threshold.
+    pub remaining_weight: U128,
+}
+
+/// Result of the dry-run of an action on the proposal.
+#[near(serializers=[json])]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct ActionSimulation {
+    /// Status the proposal would get.
+    pub status: ProposalStatus,
+    /// Votes of each role after the action. Roles that cover everyone are skipped.
+    pub roles: Vec<RoleTally>,
+}
+
+/// This is format of output via JSON for the treasury.
+#[near(serializers=[json])]
+pub struct TreasuryOutput {
+    /// Amount of $NEAR that can be spent, see `get_available_amount`.
+    pub available_amount: NearToken,
+    /// Total amount of $NEAR staked in the validator staking pools.
+    pub staked_amount: NearToken,
+    /// Total amount of $NEAR unstaked, but not withdrawn from the staking pools yet.
+    pub unstaked_amount: NearToken,
+    /// Amounts per staking pool.
+    pub pools: HashMap<AccountId, PoolBalance>,
+    /// Amounts of the registered fungible tokens deposited with `ft_transfer_call`
+    /// and not paid out yet, for the requested page of the tokens.
+    pub tokens: HashMap<AccountId, U128>,
+}
+
 #[near]
 impl Contract {
     /// Returns semver of this contract.
@@ -38,6 +101,14 @@ impl Contract {
         self.policy.get().unwrap().to_policy().clone()
     }
 
+    /// Returns problems found in the given policy, e.g. before proposing to change the policy to it.
+    pub fn validate_policy(&self, policy: VersionedPolicy) -> Vec<PolicyFinding> {
+        policy
+            .upgrade()
+            .to_policy()
+            .validate(self.staking_id.is_some())
+    }
+
     /// Returns staking contract if available. Otherwise returns empty.
     pub fn get_staking_contract(self) -> String {
         self.staking_id.map(String::from).unwrap_or_default()
@@ -60,11 +131,43 @@ impl Contract {
             .saturating_sub(self.locked_amount)
     }
 
+    /// Returns available amount of $NEAR together with amounts in the staking pools
+    /// and the tracked amounts of fungible tokens, paginated by `from_index` and `limit`.
+    pub fn get_treasury(&self, from_index: u64, limit: u64) -> TreasuryOutput {
+        let token_ids = self.token_balances.keys_as_vector();
+        let balances = self.token_balances.values_as_vector();
+        let pools: HashMap<AccountId, PoolBalance> = self.pool_balances.iter().collect();
+        TreasuryOutput {
+            available_amount: self.get_available_amount(),
+            staked_amount: NearToken::from_yoctonear(pools.values().map(|b| b.staked.0).sum()),
+            unstaked_amount: NearToken::from_yoctonear(pools.values().map(|b| b.unstaked.0).sum()),
+            pools,
+            tokens: (from_index..min(from_index + limit, token_ids.len()))
+                .filter_map(|index| Some((token_ids.get(index)?, U128(balances.get(index)?))))
+                .collect(),
+        }
+    }
+
+    /// Returns amount of given fungible token locked for proposal bonds.
+    pub fn get_locked_token_amount(&self, token_id: AccountId) -> U128 {
+        U128(
+            self.locked_token_amounts
+                .get(&token_id)
+                .copied()
+                .unwrap_or_default(),
+        )
+    }
+
     /// Returns total delegated stake.
     pub fn delegation_total_supply(&self) -> U128 {
         U128(self.total_delegation_amount)
     }
 
+    /// Returns sum of the square roots of all delegations, the total weight of quadratic voting.
+    pub fn delegation_quadratic_total_supply(&self) -> U128 {
+        U128(self.total_quadratic_delegation_amount)
+    }
+
     /// Returns delegated stake to given account.
     pub fn delegation_balance_of(&self, account_id: AccountId) -> U128 {
         U128(self.delegations.get(&account_id).unwrap_or_default())
@@ -104,6 +207,95 @@ impl Contract {
         }
     }
 
+    /// Dry-runs given action of given account on the proposal, nothing is written to the state.
+    /// Fails the same way `act_proposal` would, e.g. if the account has no permission.
+    /// Supports vote actions and `Finalize`. Unlike `act_proposal`, finalizing a proposal
+    /// that is still in progress doesn't fail and returns `InProgress`.
+    pub fn simulate_action(
+        &self,
+        id: u64,
+        account_id: AccountId,
+        action: Action,
+    ) -> ActionSimulation {
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        let (roles, allowed) = policy.can_execute_action(
+            self.internal_user_info_of(account_id.clone()),
+            &proposal.kind,
+            &action,
+        );
+        assert!(allowed, "ERR_PERMISSION_DENIED");
+        let status = match action {
+            Action::VoteApprove | Action::VoteReject | Action::VoteRemove => {
+                assert!(
+                    matches!(proposal.status, ProposalStatus::InProgress),
+                    "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
+                );
+                proposal.update_votes(
+                    &account_id,
+                    &roles,
+                    Vote::from(action),
+                    &policy,
+                    self.get_user_weight(&account_id),
+                );
+                policy.proposal_status(
+                    &proposal,
+                    roles,
+                    self.total_delegation_amount,
+                    self.total_quadratic_delegation_amount,
+                )
+            }
+            Action::Finalize if proposal.status == ProposalStatus::Pending => {
+                ProposalStatus::Approved
+            }
+            Action::Finalize if !policy.can_retry(&proposal) => ProposalStatus::Expired,
+            Action::Finalize => policy.proposal_status(
+                &proposal,
+                policy.roles.iter().map(|r| r.name.clone()).collect(),
+                self.total_delegation_amount,
+                self.total_quadratic_delegation_amount,
+            ),
+            _ => env::panic_str("ERR_WRONG_ACTION"),
+        };
+        let status = if status == ProposalStatus::Approved {
+            self.internal_approved_status(&policy, &proposal)
+        } else {
+            status
+        };
+        let defaults = [U128(0); 3];
+        let roles = policy
+            .roles
+            .iter()
+            .filter_map(|role_info| {
+                let (total_weight, threshold) = policy.internal_get_role_threshold(
+                    role_info,
+                    &proposal,
+                    self.total_delegation_amount,
+                    self.total_quadratic_delegation_amount,
+                )?;
+                let vote_counts = *proposal
+                    .vote_counts
+                    .get(&role_info.name)
+                    .unwrap_or(&defaults);
+                Some(RoleTally {
+                    role: role_info.name.clone(),
+                    vote_counts,
+                    total_weight: U128(total_weight),
+                    threshold: U128(threshold),
+                    remaining_weight: U128(
+                        threshold.saturating_sub(vote_counts[Vote::Approve as usize].0),
+                    ),
+                })
+            })
+            .collect();
+        ActionSimulation { status, roles }
+    }
+
+    /// Get summary of given pruned proposal.
+    pub fn get_pruned_proposal(&self, id: u64) -> ProposalSummary {
+        self.pruned_proposals.get(&id).expect("ERR_NO_PROPOSAL")
+    }
+
     /// Get given bounty by id.
     pub fn get_bounty(&self, id: u64) -> BountyOutput {
         let bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY");
@@ -140,6 +332,72 @@ impl Contract {
         self.bounty_claims_count.get(&id).unwrap_or_default()
     }
 
+    /// Get given recurring payment by id.
+    pub fn get_recurring_payment(&self, id: u64) -> RecurringPaymentOutput {
+        let recurring_payment = self
+            .recurring_payments
+            .get(&id)
+            .expect("ERR_NO_RECURRING_PAYMENT");
+        RecurringPaymentOutput {
+            id,
+            recurring_payment,
+        }
+    }
+
+    /// Get number of recurring payments.
+    pub fn get_last_recurring_payment_id(&self) -> u64 {
+        self.last_recurring_payment_id
+    }
+
+    /// Get allowance by id.
+    pub fn get_allowance(&self, id: u64) -> AllowanceOutput {
+        let allowance = self.allowances.get(&id).expect("ERR_NO_ALLOWANCE");
+        AllowanceOutput {
+            id,
+            remaining: U128(allowance.remaining()),
+            allowance,
+        }
+    }
+
+    /// Get number of allowances.
+    pub fn get_last_allowance_id(&self) -> u64 {
+        self.last_allowance_id
+    }
+
+    /// Get `limit` of allowances from given index, skipping revoked and expired ones.
+    pub fn get_allowances(&self, from_index: u64, limit: u64) -> Vec<AllowanceOutput> {
+        (from_index..min(from_index + limit, self.last_allowance_id))
+            .filter_map(|id| {
+                self.allowances
+                    .get(&id)
+                    .filter(|allowance| !allowance.is_expired())
+                    .map(|allowance| AllowanceOutput {
+                        id,
+                        remaining: U128(allowance.remaining()),
+                        allowance,
+                    })
+            })
+            .collect()
+    }
+
+    /// Get `limit` of recurring payments from given index.
+    pub fn get_recurring_payments(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<RecurringPaymentOutput> {
+        (from_index..min(from_index + limit, self.last_recurring_payment_id))
+            .filter_map(|id| {
+                self.recurring_payments
+                    .get(&id)
+                    .map(|recurring_payment| RecurringPaymentOutput {
+                        id,
+                        recurring_payment,
+                    })
+            })
+            .collect()
+    }
+
     pub fn get_actions_log(&self) -> &VecDeque<ActionLog> {
         &self.actions_log
     }
@@ -5,7 +5,7 @@ use near_sdk::serde_json::{Value, json};
 
 use near_api::{AccountId, FTBalance, Signer, Staking};
 use near_api::{NearToken, W_NEAR_BALANCE};
-use sputnikdao2::action_log::ActionLog;
+use sputnikdao2::action_log::{ActionLog, LoggedAction};
 use std::collections::HashMap;
 
 mod utils;
@@ -139,6 +139,14 @@ async fn test_multi_council() -> testresult::TestResult {
         proposal_period: U64::from(1_000_000_000 * 60 * 60 * 24 * 7),
         bounty_bond: NearToken::from_near(1),
         bounty_forgiveness_period: U64::from(1_000_000_000 * 60 * 60 * 24),
+        execution_delay: HashMap::default(),
+        proposal_bond_overrides: HashMap::default(),
+        proposal_period_overrides: HashMap::default(),
+        proposal_bond_token: None,
+        hub_id: None,
+        proposal_retention_period: None,
+        failed_proposal_max_retries: None,
+        ft_storage_deposit: None,
     };
     add_proposal(
         &ctx,
@@ -148,6 +156,7 @@ async fn test_multi_council() -> testresult::TestResult {
             kind: ProposalKind::ChangePolicy {
                 policy: VersionedPolicy::Current(new_policy.clone()),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -628,6 +637,7 @@ async fn test_create_dao_and_use_token() -> testresult::TestResult {
             kind: ProposalKind::SetStakingContract {
                 staking_id: staking.0.clone(),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -1024,7 +1034,7 @@ async fn test_actions_log() -> testresult::TestResult {
         ActionLog {
             account_id: "dao.sandbox".parse()?,
             proposal_id: proposal_id.into(),
-            action: Action::AddProposal,
+            action: LoggedAction::AddProposal,
             block_height: action_log.block_height // It is uncertain because of async block creation
         }
     );
@@ -1066,7 +1076,7 @@ async fn test_actions_log() -> testresult::TestResult {
         ActionLog {
             account_id: "user0.sandbox".parse()?,
             proposal_id: proposal_id.into(),
-            action: Action::VoteApprove,
+            action: LoggedAction::VoteApprove,
             block_height: action_log.block_height, // It is uncertain because of async block creation
         }
     );
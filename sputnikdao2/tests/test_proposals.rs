@@ -126,6 +126,14 @@ async fn test_bob_cannot_add_proposals() -> testresult::TestResult {
         proposal_period: period,
         bounty_bond: NearToken::from_near(1),
         bounty_forgiveness_period: period,
+        execution_delay: HashMap::default(),
+        proposal_bond_overrides: HashMap::default(),
+        proposal_period_overrides: HashMap::default(),
+        proposal_bond_token: None,
+        hub_id: None,
+        proposal_retention_period: None,
+        failed_proposal_max_retries: None,
+        ft_storage_deposit: None,
     };
 
     // Bob adds a ChangePolicy proposal (everyone can add proposals initially)
@@ -138,6 +146,7 @@ async fn test_bob_cannot_add_proposals() -> testresult::TestResult {
             kind: ProposalKind::ChangePolicy {
                 policy: VersionedPolicy::Current(new_policy),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -161,8 +170,17 @@ async fn test_bob_cannot_add_proposals() -> testresult::TestResult {
                     proposal_period: period,
                     bounty_bond: NearToken::from_near(1),
                     bounty_forgiveness_period: period,
+                    execution_delay: HashMap::default(),
+                    proposal_bond_overrides: HashMap::default(),
+                    proposal_period_overrides: HashMap::default(),
+                    proposal_bond_token: None,
+                    hub_id: None,
+                    proposal_retention_period: None,
+                    failed_proposal_max_retries: None,
+                    ft_storage_deposit: None,
                 }),
             },
+            depends_on: vec![],
         },
     )
     .await;
@@ -230,6 +248,14 @@ async fn test_proposal_change_policy() -> testresult::TestResult {
         proposal_period: period,
         bounty_bond: NearToken::from_near(1),
         bounty_forgiveness_period: period,
+        execution_delay: HashMap::default(),
+        proposal_bond_overrides: HashMap::default(),
+        proposal_period_overrides: HashMap::default(),
+        proposal_bond_token: None,
+        hub_id: None,
+        proposal_retention_period: None,
+        failed_proposal_max_retries: None,
+        ft_storage_deposit: None,
     };
 
     let proposal_id: u64 = add_proposal_as(
@@ -241,6 +267,7 @@ async fn test_proposal_change_policy() -> testresult::TestResult {
             kind: ProposalKind::ChangePolicy {
                 policy: VersionedPolicy::Current(correct_policy.clone()),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -381,6 +408,7 @@ async fn test_proposal_set_staking_contract() -> testresult::TestResult {
             kind: ProposalKind::SetStakingContract {
                 staking_id: staking.0.clone(),
             },
+            depends_on: vec![],
         },
     )
     .await;
@@ -411,6 +439,7 @@ async fn test_voting_only_for_councils() -> testresult::TestResult {
                     metadata: Base64VecU8(vec![]),
                 },
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -484,6 +513,7 @@ async fn test_act_proposal_correct_kind() -> testresult::TestResult {
             kind: ProposalKind::ChangeConfig {
                 config: correct_config,
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -868,6 +898,87 @@ async fn test_callback_function_call() -> testresult::TestResult {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_callback_function_call_chain() -> testresult::TestResult {
+    let (ctx, dao) = setup_dao().await?;
+    let alice = create_named_account(&ctx, "alice", 100).await?;
+
+    let test_token = setup_test_token(&ctx).await?;
+
+    let action = |method_name: &str, amount: &str| {
+        json!({
+            "method_name": method_name,
+            "args": general_purpose::STANDARD.encode(format!(
+                r#"{{"account_id": "{alice}", "amount": "{amount}"}}"#
+            )),
+            "deposit": "0",
+            "gas": 10_000_000_000_000u64
+        })
+    };
+    let ft_balance_of_alice = || async {
+        test_token
+            .call_function("ft_balance_of", json!({"account_id": alice}))
+            .read_only::<U128>()
+            .fetch_from(&ctx.sandbox_network)
+            .await
+            .map(|balance| balance.data.0)
+    };
+
+    // First step fails (alice is not registered), so the second step must not be executed.
+    for (steps, status, balance) in [
+        (
+            vec![action("burn", "10"), action("mint", "10")],
+            ProposalStatus::Failed,
+            0,
+        ),
+        (
+            vec![action("mint", "10"), action("burn", "5")],
+            ProposalStatus::Approved,
+            5,
+        ),
+    ] {
+        let proposal_id: u64 = dao
+            .call_function(
+                "add_proposal",
+                json!({
+                    "proposal": {
+                        "description": "call chain",
+                        "kind": {
+                            "FunctionCallChain": {
+                                "steps": steps
+                                    .into_iter()
+                                    .map(|action| json!({
+                                        "receiver_id": test_token.0.to_string(),
+                                        "actions": [action]
+                                    }))
+                                    .collect::<Vec<_>>()
+                            }
+                        }
+                    }
+                }),
+            )
+            .transaction()
+            .deposit(NearToken::from_near(1))
+            .with_signer(dao.0.clone(), ctx.signer.clone())
+            .send_to(&ctx.sandbox_network)
+            .await?
+            .json()?;
+
+        vote(&ctx, vec![&ctx.root], &dao, proposal_id).await?;
+
+        let proposal: ProposalOutput = dao
+            .call_function("get_proposal", json!({"id": proposal_id}))
+            .read_only()
+            .fetch_from(&ctx.sandbox_network)
+            .await?
+            .data;
+        assert_eq!(proposal.proposal.status, status);
+        assert_eq!(ft_balance_of_alice().await?, balance);
+    }
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_remove_blob() -> testresult::TestResult {
     let (ctx, dao) = setup_dao().await?;
@@ -949,3 +1060,87 @@ async fn test_remove_blob() -> testresult::TestResult {
 
     Ok(())
 }
+
+/// Sub-DAO is created through the real factory, which returns the result of the sub-DAO `new`.
+#[tokio::test]
+async fn test_create_sub_dao() -> testresult::TestResult {
+    let (ctx, factory) = setup_factory().await?;
+    let root = ctx.root.clone();
+
+    let config = Config {
+        name: "testdao".to_string(),
+        purpose: "to test".to_string(),
+        metadata: Base64VecU8(vec![]),
+    };
+    let policy = VersionedPolicy::Default(vec![root.clone()]);
+    let params = json!({ "config": config, "policy": policy }).to_string();
+    factory
+        .call_function(
+            "create",
+            json!({
+                "name": "testdao",
+                "args": Base64VecU8(params.into())
+            }),
+        )
+        .transaction()
+        .deposit(NearToken::from_near(20))
+        .max_gas()
+        .with_signer(root.clone(), ctx.signer.clone())
+        .send_to(&ctx.sandbox_network)
+        .await?
+        .into_result()?;
+    let dao = near_api::Contract(format!("testdao.{}", factory.0).parse()?);
+
+    let proposal_id: u64 = dao
+        .call_function(
+            "add_proposal",
+            json!({ "proposal": ProposalInput {
+                description: "create working group".to_string(),
+                kind: ProposalKind::CreateSubDao {
+                    name: "group".parse()?,
+                    config: config.clone(),
+                    policy,
+                    deposit: U128(NearToken::from_near(5).as_yoctonear()),
+                    parent_role: Some("council".to_string()),
+                },
+                depends_on: vec![],
+            }}),
+        )
+        .transaction()
+        .deposit(NearToken::from_near(1))
+        .with_signer(root.clone(), ctx.signer.clone())
+        .send_to(&ctx.sandbox_network)
+        .await?
+        .json()?;
+    vote(&ctx, vec![&root], &dao, proposal_id).await?;
+
+    let proposal: ProposalOutput = dao
+        .call_function("get_proposal", json!({ "id": proposal_id }))
+        .read_only()
+        .fetch_from(&ctx.sandbox_network)
+        .await?
+        .data;
+    assert_eq!(proposal.proposal.status, ProposalStatus::Approved);
+
+    let sub_dao_id: near_api::AccountId = format!("group.{}", factory.0).parse()?;
+    let sub_daos: Vec<near_api::AccountId> = dao
+        .call_function("get_sub_daos", json!({ "from_index": 0, "limit": 10 }))
+        .read_only()
+        .fetch_from(&ctx.sandbox_network)
+        .await?
+        .data;
+    assert_eq!(sub_daos, [sub_dao_id.clone()]);
+
+    // The sub-DAO is initialized, with the parent DAO in its council.
+    let sub_dao_policy: Policy = near_api::Contract(sub_dao_id)
+        .call_function("get_policy", json!({}))
+        .read_only()
+        .fetch_from(&ctx.sandbox_network)
+        .await?
+        .data;
+    assert!(
+        matches!(&sub_dao_policy.roles[1].kind, RoleKind::Group(members) if members.contains(&dao.0))
+    );
+
+    Ok(())
+}
@@ -142,6 +142,7 @@ async fn test_proposal_action_types() -> testresult::TestResult {
             kind: ProposalKind::ChangeConfig {
                 config: config.clone(),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -201,6 +202,7 @@ async fn test_proposal_action_types() -> testresult::TestResult {
             kind: ProposalKind::ChangeConfig {
                 config: config.clone(),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -257,6 +259,26 @@ async fn test_proposal_action_types() -> testresult::TestResult {
     .await?
     .into_result()?;
 
+    // Finalize a non-expired, non-failed proposal → ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED
+    let result = dao
+        .call_function(
+            "act_proposal",
+            json!({
+                "id": proposal_id,
+                "action": Action::Finalize,
+                "proposal": proposal_kind.clone()
+            }),
+        )
+        .transaction()
+        .with_signer(alice.clone(), ctx.signer.clone())
+        .send_to(&ctx.sandbox_network)
+        .await?;
+    assert!(
+        format!("{:?}", result.failures()).contains("ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED"),
+        "Expected ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED: {:?}",
+        result.failures()
+    );
+
     dao.call_function(
         "act_proposal",
         json!({
@@ -288,26 +310,8 @@ async fn test_proposal_action_types() -> testresult::TestResult {
     assert_eq!(proposal_val["votes"][alice.to_string()], json!("Remove"));
     assert_eq!(proposal_val["votes"][user1.to_string()], json!("Approve"));
     assert_eq!(proposal_val["votes"][user2.to_string()], json!("Reject"));
-
-    // Finalize a non-expired, non-failed proposal → ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED
-    let result = dao
-        .call_function(
-            "act_proposal",
-            json!({
-                "id": proposal_id,
-                "action": Action::Finalize,
-                "proposal": proposal_kind
-            }),
-        )
-        .transaction()
-        .with_signer(alice.clone(), ctx.signer.clone())
-        .send_to(&ctx.sandbox_network)
-        .await?;
-    assert!(
-        format!("{:?}", result.failures()).contains("ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED"),
-        "Expected ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED: {:?}",
-        result.failures()
-    );
+    // With 3 out of 4 votes needed, approval is no longer possible.
+    assert_eq!(proposal_val["status"], json!("Rejected"));
 
     Ok(())
 }
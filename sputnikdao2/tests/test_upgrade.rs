@@ -163,6 +163,7 @@ async fn test_upgrade_other() -> testresult::TestResult {
                 method_name: "upgrade".to_string(),
                 hash,
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -170,6 +170,7 @@ async fn test_view_get_locked_storage_amount() -> testresult::TestResult {
         ProposalInput {
             description: "adding some bytes".to_string(),
             kind: ProposalKind::Vote,
+            depends_on: vec![],
         },
     )
     .await
@@ -209,6 +210,7 @@ async fn test_view_get_available_amount() -> testresult::TestResult {
         ProposalInput {
             description: "adding some bytes".to_string(),
             kind: ProposalKind::Vote,
+            depends_on: vec![],
         },
     )
     .await
@@ -318,6 +320,7 @@ async fn test_view_proposal_methods() -> testresult::TestResult {
             kind: ProposalKind::ChangeConfig {
                 config: config.clone(),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -310,6 +310,7 @@ pub async fn add_member_proposal(
                 member_id,
                 role: "council".to_string(),
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -334,6 +335,7 @@ pub async fn add_transfer_proposal(
                 amount: U128(amount),
                 msg,
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -360,6 +362,7 @@ pub async fn add_bounty_proposal(ctx: &TestContext, dao: &Contract) -> Execution
                     max_deadline: U64(block_timestamp + 10_000_000_000),
                 },
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -476,6 +479,7 @@ pub async fn propose_bounty_ft(
                     max_deadline: U64(DEADLINE),
                 },
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -503,6 +507,7 @@ pub async fn propose_bounty_near(
                     max_deadline: U64(DEADLINE),
                 },
             },
+            depends_on: vec![],
         },
     )
     .await
@@ -641,6 +646,7 @@ pub async fn set_staking_contract(
             kind: ProposalKind::SetStakingContract {
                 staking_id: staking_id.clone(),
             },
+            depends_on: vec![],
         },
     )
     .await